@@ -0,0 +1,136 @@
+use crate::error::{Error, Result};
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::{
+    collections::HashSet,
+    fmt::{Display, Formatter},
+    fs,
+    path::{Path, PathBuf},
+};
+
+#[derive(Serialize, Deserialize, Clone, PartialEq, Debug)]
+pub struct Attachment {
+    pub name: String,
+    pub size: u64,
+    /// Hex encoded sha256 of the content, the key of its blob.
+    pub hash: String,
+}
+
+impl Display for Attachment {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{} ({})", self.name, human_size(self.size))
+    }
+}
+
+/// Content-addressed files, laid out as `<dir>/<hash[..2]>/<hash>`.
+pub struct Blobs {
+    dir: PathBuf,
+}
+
+impl Blobs {
+    pub fn new(dir: impl AsRef<Path>) -> Blobs {
+        Blobs {
+            dir: dir.as_ref().to_path_buf(),
+        }
+    }
+    pub fn path(&self, hash: &str) -> PathBuf {
+        self.dir.join(&hash[..2]).join(hash)
+    }
+    /// Copies `file` into the blob area, sharing the blob with identical files.
+    pub fn put(&self, file: impl AsRef<Path>) -> Result<Attachment> {
+        let file = file.as_ref();
+        let name = file
+            .file_name()
+            .map(|n| n.to_string_lossy().into_owned())
+            .ok_or_else(|| Error::StringError(format!("`{}` is not a file", file.display())))?;
+        let content = fs::read(file)?;
+        let hash: String = Sha256::digest(&content)
+            .iter()
+            .map(|b| format!("{:02x}", b))
+            .collect();
+        let path = self.path(&hash);
+        if !path.exists() {
+            fs::create_dir_all(path.parent().unwrap())?;
+            let temp = path.with_extension("partial");
+            fs::write(&temp, &content)?;
+            fs::rename(&temp, &path)?;
+        }
+        Ok(Attachment {
+            name,
+            size: content.len() as u64,
+            hash,
+        })
+    }
+    /// Copies the blob of `attachment` to `dest`, into it if it is a directory.
+    pub fn get(&self, attachment: &Attachment, dest: impl AsRef<Path>) -> Result<PathBuf> {
+        let mut dest = dest.as_ref().to_path_buf();
+        if dest.is_dir() {
+            dest.push(&attachment.name);
+        }
+        fs::copy(self.path(&attachment.hash), &dest)?;
+        Ok(dest)
+    }
+    /// Removes blobs whose hash is not in `referenced`, returns how many.
+    pub fn gc(&self, referenced: &HashSet<String>) -> Result<usize> {
+        if !self.dir.exists() {
+            return Ok(0);
+        }
+        let mut removed = 0;
+        for shard in fs::read_dir(&self.dir)? {
+            let shard = shard?.path();
+            if !shard.is_dir() {
+                continue;
+            }
+            for blob in fs::read_dir(&shard)? {
+                let blob = blob?.path();
+                let hash = blob.file_name().unwrap().to_string_lossy().into_owned();
+                if !referenced.contains(&hash) {
+                    fs::remove_file(&blob)?;
+                    removed += 1;
+                }
+            }
+            if fs::read_dir(&shard)?.next().is_none() {
+                fs::remove_dir(&shard)?;
+            }
+        }
+        Ok(removed)
+    }
+}
+
+pub fn human_size(size: u64) -> String {
+    const UNITS: [&str; 4] = ["B", "KB", "MB", "GB"];
+    let mut size = size as f64;
+    let mut unit = 0;
+    while size >= 1024.0 && unit < UNITS.len() - 1 {
+        size /= 1024.0;
+        unit += 1;
+    }
+    if unit == 0 {
+        format!("{} {}", size, UNITS[unit])
+    } else {
+        format!("{:.1} {}", size, UNITS[unit])
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use tempfile::TempDir;
+    #[test]
+    fn test_basis() {
+        let temp_dir = TempDir::new().unwrap();
+        let blobs = Blobs::new(temp_dir.path().join("attachments"));
+        let file = temp_dir.path().join("log.txt");
+        fs::write(&file, "hello").unwrap();
+        let attachment = blobs.put(&file).unwrap();
+        assert_eq!(attachment.name, "log.txt");
+        assert_eq!(attachment.size, 5);
+        assert!(blobs.path(&attachment.hash).exists());
+        let mut referenced = HashSet::new();
+        referenced.insert(attachment.hash.clone());
+        assert_eq!(blobs.gc(&referenced).unwrap(), 0);
+        assert_eq!(blobs.gc(&HashSet::new()).unwrap(), 1);
+        assert!(!blobs.path(&attachment.hash).exists());
+        assert_eq!(human_size(1536), "1.5 KB");
+    }
+}
@@ -0,0 +1,156 @@
+use crate::error::{Error, Result};
+use std::{
+    io::Write,
+    process::{Command, Stdio},
+};
+
+/// A part of an item to copy.
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum Clip {
+    Summary,
+    Detail,
+    Url,
+}
+
+impl Clip {
+    /// The part picked by the key pressed after `y`.
+    pub fn from_key(c: char) -> Option<Clip> {
+        match c {
+            's' => Some(Clip::Summary),
+            'd' => Some(Clip::Detail),
+            'u' => Some(Clip::Url),
+            _ => None,
+        }
+    }
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            Clip::Summary => "summary",
+            Clip::Detail => "detail",
+            Clip::Url => "url",
+        }
+    }
+}
+
+pub const CLIP_HELP: &str = "copy: s summary, d detail, u url";
+
+/// The system clipboard, through external commands.
+#[derive(Clone)]
+pub struct Clipboard {
+    copy: String,
+    paste: String,
+}
+
+impl Clipboard {
+    /// Empty commands pick the usual tool of the platform.
+    pub fn new(copy: &str, paste: &str) -> Clipboard {
+        let (default_copy, default_paste) = commands();
+        let pick = |cmd: &str, default: &str| {
+            if cmd.trim().is_empty() {
+                default.to_owned()
+            } else {
+                cmd.to_owned()
+            }
+        };
+        Clipboard {
+            copy: pick(copy, default_copy),
+            paste: pick(paste, default_paste),
+        }
+    }
+
+    pub fn copy(&self, text: &str) -> Result<()> {
+        let mut child = command(&self.copy)?
+            .stdin(Stdio::piped())
+            .stdout(Stdio::null())
+            .spawn()
+            .map_err(|e| failed(&self.copy, e))?;
+        // the stdin handle drops here, so the command sees the end of input
+        let written = child.stdin.take().unwrap().write_all(text.as_bytes());
+        let status = child.wait()?;
+        written?;
+        if !status.success() {
+            return Err(failed(&self.copy, "non-zero exit"));
+        }
+        Ok(())
+    }
+
+    /// Copies `text`, the `part` of an item if it has one, returns the
+    /// message for the status bar.
+    pub fn copy_part(&self, part: Clip, text: Option<String>) -> String {
+        match text {
+            Some(text) => match self.copy(&text) {
+                Ok(()) => format!("copied the {}", part.name()),
+                Err(e) => e.to_string(),
+            },
+            None => format!("no {} to copy", part.name()),
+        }
+    }
+
+    pub fn paste(&self) -> Result<String> {
+        let output = command(&self.paste)?
+            .stderr(Stdio::inherit())
+            .output()
+            .map_err(|e| failed(&self.paste, e))?;
+        if !output.status.success() {
+            return Err(failed(&self.paste, "non-zero exit"));
+        }
+        Ok(String::from_utf8(output.stdout)?)
+    }
+}
+
+impl Default for Clipboard {
+    fn default() -> Clipboard {
+        Clipboard::new("", "")
+    }
+}
+
+fn command(line: &str) -> Result<Command> {
+    let mut words = line.split_whitespace();
+    let mut cmd = Command::new(
+        words
+            .next()
+            .ok_or_else(|| Error::StringError("empty clipboard command".to_owned()))?,
+    );
+    cmd.args(words);
+    Ok(cmd)
+}
+
+fn failed(cmd: &str, e: impl std::fmt::Display) -> Error {
+    Error::StringError(format!("clipboard command `{}` failed: {}", cmd, e))
+}
+
+#[cfg(target_os = "macos")]
+fn commands() -> (&'static str, &'static str) {
+    ("pbcopy", "pbpaste")
+}
+
+#[cfg(target_os = "windows")]
+fn commands() -> (&'static str, &'static str) {
+    ("clip", "powershell -NoProfile -Command Get-Clipboard")
+}
+
+#[cfg(not(any(target_os = "macos", target_os = "windows")))]
+fn commands() -> (&'static str, &'static str) {
+    if std::env::var_os("WAYLAND_DISPLAY").is_some() {
+        ("wl-copy", "wl-paste --no-newline")
+    } else {
+        (
+            "xclip -selection clipboard",
+            "xclip -selection clipboard -o",
+        )
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    #[test]
+    fn test_basis() {
+        let clipboard = Clipboard::new("cat", "echo copied text");
+        clipboard.copy("text").unwrap();
+        assert_eq!(clipboard.paste().unwrap(), "copied text\n");
+        assert!(Clipboard::new("false", "").copy("text").is_err());
+        assert_eq!(clipboard.copy_part(Clip::Url, None), "no url to copy");
+        assert_eq!(Clip::from_key('u'), Some(Clip::Url));
+    }
+}
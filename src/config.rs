@@ -0,0 +1,87 @@
+use crate::error::{Error, Result};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::collections::BTreeMap;
+
+#[derive(Serialize, Deserialize, Clone)]
+#[serde(default, rename_all = "kebab-case")]
+pub struct Config {
+    /// Mark a todo done once every subtask of it is checked.
+    pub auto_complete: bool,
+    /// Key binding preset of pickers: `default`, `vi` or `emacs`.
+    pub keymap: String,
+    /// Extra picker bindings on top of the preset, e.g. `{"ctrl-j": "down"}`.
+    pub keys: BTreeMap<String, String>,
+    /// Capture the mouse in pickers, the viewer and the tui, turn it off to
+    /// keep the terminal's own selection for copy-paste.
+    pub mouse: bool,
+    /// Color theme: `dark`, `light`, `high-contrast` or `plain`.
+    pub theme: String,
+    /// Status icons: `emoji` or `ascii`.
+    pub icons: String,
+    /// Command that takes text to copy on stdin, `xclip`, `wl-copy` or
+    /// `pbcopy` when empty.
+    pub copy_command: String,
+    /// Command that prints the clipboard, `xclip -o`, `wl-paste` or
+    /// `pbpaste` when empty.
+    pub paste_command: String,
+}
+
+impl Default for Config {
+    fn default() -> Config {
+        Config {
+            auto_complete: false,
+            keymap: "default".to_owned(),
+            keys: BTreeMap::new(),
+            mouse: true,
+            theme: "dark".to_owned(),
+            icons: "emoji".to_owned(),
+            copy_command: String::new(),
+            paste_command: String::new(),
+        }
+    }
+}
+
+impl Config {
+    pub fn get(&self, key: &str) -> Result<Value> {
+        serde_json::to_value(self)?
+            .get(key)
+            .cloned()
+            .ok_or_else(|| Error::StringError(format!("unknown config key `{}`", key)))
+    }
+    /// Sets `key` from its textual form, which is read as JSON when possible
+    /// and as a plain string otherwise.
+    pub fn set(&mut self, key: &str, value: &str) -> Result<()> {
+        let mut config = serde_json::to_value(&*self)?;
+        let slot = config
+            .get_mut(key)
+            .ok_or_else(|| Error::StringError(format!("unknown config key `{}`", key)))?;
+        *slot = serde_json::from_str(value).unwrap_or_else(|_| Value::String(value.to_owned()));
+        *self = serde_json::from_value(config)
+            .map_err(|e| Error::StringError(format!("invalid value for `{}`: {}", key, e)))?;
+        Ok(())
+    }
+    pub fn entries(&self) -> Result<Vec<(String, Value)>> {
+        Ok(match serde_json::to_value(self)? {
+            Value::Object(map) => map.into_iter().collect(),
+            _ => vec![],
+        })
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    #[test]
+    fn test_basis() {
+        let mut config = Config::default();
+        config.set("auto-complete", "true").unwrap();
+        assert!(config.auto_complete);
+        assert!(config.set("auto-complete", "yes").is_err());
+        assert!(config.set("no-such-key", "1").is_err());
+        config.set("keys", r#"{"ctrl-j": "down"}"#).unwrap();
+        assert_eq!(config.keys["ctrl-j"], "down");
+        config.set("theme", "light").unwrap();
+        assert_eq!(config.theme, "light");
+    }
+}
@@ -0,0 +1,171 @@
+use serde::{Deserialize, Serialize};
+use std::{
+    convert::TryFrom,
+    fmt::{Display, Formatter, Result},
+    str::FromStr,
+    time::{Duration, SystemTime, UNIX_EPOCH},
+};
+
+const SECS_PER_DAY: u64 = 24 * 60 * 60;
+const SECS_PER_HOUR: u64 = 60 * 60;
+
+/// A calendar day, counted in days since 1970-01-01 (UTC).
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
+#[serde(try_from = "String", into = "String")]
+pub struct Date(i64);
+
+impl Date {
+    pub fn today() -> Date {
+        Date::from_time(SystemTime::now())
+    }
+    pub fn from_time(time: SystemTime) -> Date {
+        match time.duration_since(UNIX_EPOCH) {
+            Ok(d) => Date((d.as_secs() / SECS_PER_DAY) as i64),
+            Err(e) => Date(-(e.duration().as_secs().div_ceil(SECS_PER_DAY) as i64)),
+        }
+    }
+    pub fn to_time(self) -> SystemTime {
+        let secs = self.0.unsigned_abs() * SECS_PER_DAY;
+        if self.0 >= 0 {
+            UNIX_EPOCH + Duration::from_secs(secs)
+        } else {
+            UNIX_EPOCH - Duration::from_secs(secs)
+        }
+    }
+    pub fn from_ymd(y: i64, m: u32, d: u32) -> Option<Date> {
+        if !(1..=12).contains(&m) || d == 0 || d > days_in_month(y, m) {
+            return None;
+        }
+        // days_from_civil, see http://howardhinnant.github.io/date_algorithms.html
+        let y = if m <= 2 { y - 1 } else { y };
+        let era = y.div_euclid(400);
+        let yoe = y - era * 400;
+        let mp = (m as i64 + 9) % 12;
+        let doy = (153 * mp + 2) / 5 + d as i64 - 1;
+        let doe = yoe * 365 + yoe / 4 - yoe / 100 + doy;
+        Some(Date(era * 146097 + doe - 719468))
+    }
+    pub fn ymd(self) -> (i64, u32, u32) {
+        let z = self.0 + 719468;
+        let era = z.div_euclid(146097);
+        let doe = z - era * 146097;
+        let yoe = (doe - doe / 1460 + doe / 36524 - doe / 146096) / 365;
+        let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+        let mp = (5 * doy + 2) / 153;
+        let d = (doy - (153 * mp + 2) / 5 + 1) as u32;
+        let m = if mp < 10 { mp + 3 } else { mp - 9 } as u32;
+        let y = yoe + era * 400 + if m <= 2 { 1 } else { 0 };
+        (y, m, d)
+    }
+    /// Day of the week, 0 is Monday.
+    pub fn weekday(self) -> u32 {
+        // 1970-01-01 is a Thursday
+        (self.0 + 3).rem_euclid(7) as u32
+    }
+    pub fn add_days(self, n: i64) -> Date {
+        Date(self.0 + n)
+    }
+    /// Moves by whole months, clamping the day to the end of the target month.
+    pub fn add_months(self, n: i64) -> Date {
+        let (y, m, d) = self.ymd();
+        let months = y * 12 + (m as i64 - 1) + n;
+        let (y, m) = (months.div_euclid(12), months.rem_euclid(12) as u32 + 1);
+        Date::from_ymd(y, m, std::cmp::min(d, days_in_month(y, m))).unwrap()
+    }
+}
+
+/// The start of the window `since` names: `7d`, `2w`, `12h` back from
+/// `now`, or a `YYYY-MM-DD` date.
+pub fn parse_since(since: &str, now: SystemTime) -> std::result::Result<SystemTime, String> {
+    let since = since.trim();
+    let start = since.char_indices().last().and_then(|(i, unit)| {
+        let n: u64 = since[..i].parse().ok()?;
+        let hours = match unit {
+            'h' => Some(n),
+            'd' => n.checked_mul(24),
+            'w' => n.checked_mul(24 * 7),
+            _ => None,
+        }?;
+        now.checked_sub(Duration::from_secs(hours.checked_mul(SECS_PER_HOUR)?))
+    });
+    match start {
+        Some(start) => Ok(start),
+        None => since.parse::<Date>().map(Date::to_time).map_err(|_| {
+            format!(
+                "invalid window `{}`, expect e.g. 7d, 2w, 12h or a date",
+                since
+            )
+        }),
+    }
+}
+
+fn days_in_month(y: i64, m: u32) -> u32 {
+    match m {
+        2 if (y % 4 == 0 && y % 100 != 0) || y % 400 == 0 => 29,
+        2 => 28,
+        4 | 6 | 9 | 11 => 30,
+        _ => 31,
+    }
+}
+
+impl Display for Date {
+    fn fmt(&self, f: &mut Formatter<'_>) -> Result {
+        let (y, m, d) = self.ymd();
+        f.pad(format!("{:04}-{:02}-{:02}", y, m, d).as_str())
+    }
+}
+
+impl FromStr for Date {
+    type Err = String;
+    fn from_str(s: &str) -> std::result::Result<Date, String> {
+        let err = || format!("invalid date `{}`, expect YYYY-MM-DD", s);
+        match s.trim() {
+            "today" => return Ok(Date::today()),
+            "tomorrow" => return Ok(Date::today().add_days(1)),
+            "yesterday" => return Ok(Date::today().add_days(-1)),
+            _ => {}
+        }
+        let mut parts = s.trim().splitn(3, '-');
+        let mut next = || parts.next().ok_or_else(err);
+        let y = next()?.parse().map_err(|_| err())?;
+        let m = next()?.parse().map_err(|_| err())?;
+        let d = next()?.parse().map_err(|_| err())?;
+        Date::from_ymd(y, m, d).ok_or_else(err)
+    }
+}
+
+impl TryFrom<String> for Date {
+    type Error = String;
+    fn try_from(s: String) -> std::result::Result<Date, String> {
+        s.parse()
+    }
+}
+
+impl From<Date> for String {
+    fn from(date: Date) -> String {
+        date.to_string()
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    #[test]
+    fn test_basis() {
+        let date: Date = "2024-02-29".parse().unwrap();
+        assert_eq!(date.ymd(), (2024, 2, 29));
+        assert_eq!(date.weekday(), 3);
+        assert_eq!(date.add_months(12).to_string(), "2025-02-28");
+        assert_eq!(Date::from_time(date.to_time()), date);
+        assert_eq!(Date(0).to_string(), "1970-01-01");
+        assert!("2023-02-29".parse::<Date>().is_err());
+        let now = date.to_time();
+        let day = Duration::from_secs(SECS_PER_DAY);
+        assert_eq!(parse_since("7d", now).unwrap(), now - day * 7);
+        assert_eq!(parse_since("1w", now).unwrap(), now - day * 7);
+        assert_eq!(parse_since("2024-02-28", now).unwrap(), now - day);
+        assert!(parse_since("7y", now).is_err());
+        assert!(parse_since("99999999999999999w", now).is_err());
+        assert!(parse_since(&format!("{}h", u64::MAX), now).is_err());
+    }
+}
@@ -0,0 +1,75 @@
+use crate::{date::Date, item::Item, status::*};
+use std::time::SystemTime;
+
+/// A Markdown report of the `items` captured, completed or otherwise
+/// updated from `start` on, grouped by type.
+pub fn digest(items: &[Item], start: SystemTime) -> String {
+    let mut text = format!("# Digest since {}\n", Date::from_time(start));
+    let mut empty = true;
+    for &ty in TALL {
+        let of_type: Vec<_> = items.iter().filter(|i| i.ty.desc() == ty).collect();
+        let created: Vec<_> = of_type.iter().filter(|i| i.created_at() >= start).collect();
+        let completed: Vec<_> = of_type
+            .iter()
+            .filter(|i| i.completed_at().is_some_and(|t| t >= start))
+            .collect();
+        let updated: Vec<_> = of_type
+            .iter()
+            .filter(|i| i.time >= start && i.created_at() < start)
+            .filter(|i| i.completed_at().is_none_or(|t| t < start))
+            .collect();
+        if created.is_empty() && completed.is_empty() && updated.is_empty() {
+            continue;
+        }
+        empty = false;
+        text += &format!("\n## {}\n", ty);
+        for (title, list) in [
+            ("Completed", completed),
+            ("Created", created),
+            ("Updated", updated),
+        ] {
+            if list.is_empty() {
+                continue;
+            }
+            text += &format!("\n### {}\n", title);
+            for item in list {
+                text += &format!("- {}", item.summary);
+                for label in &item.labels {
+                    text += &format!(" `{}`", label);
+                }
+                text += "\n";
+            }
+        }
+    }
+    if empty {
+        text += "\nNothing happened.\n";
+    }
+    text
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    #[test]
+    fn test_basis() {
+        let now: SystemTime = "2024-03-13".parse::<Date>().unwrap().to_time();
+        let day = std::time::Duration::from_secs(24 * 60 * 60);
+
+        let mut old = Item::new(Type::new(DONE), "ship".to_owned());
+        old.created = Some(now - day * 30);
+        old.completed = Some(now - day);
+        old.time = now - day;
+        let mut new = Item::new(Type::new(IDEA), "think".to_owned());
+        new.created = Some(now - day * 2);
+        new.labels = vec!["work".to_owned()];
+        let mut stale = Item::new(Type::new(TIPS), "tip".to_owned());
+        stale.created = Some(now - day * 30);
+        stale.time = now - day * 30;
+
+        let text = digest(&[old, new, stale], now - day * 7);
+        assert!(text.contains("## todo\n\n### Completed\n- ship\n"));
+        assert!(text.contains("## idea\n\n### Created\n- think `work`\n"));
+        assert!(!text.contains("tip"));
+        assert!(digest(&[], now).contains("Nothing happened."));
+    }
+}
@@ -1,8 +1,11 @@
+#![allow(non_local_definitions)]
+
 use std::io;
 use std::string::FromUtf8Error;
 
 use failure::Fail;
 
+#[allow(clippy::enum_variant_names)]
 #[derive(Fail, Debug)]
 pub enum Error {
     #[fail(display = "{}", _0)]
@@ -0,0 +1,339 @@
+use crate::{
+    date::{parse_since, Date},
+    error::{Error, Result},
+    interaction::Searchable,
+    query::{self, Expr},
+    status::*,
+    store::ItemPair,
+};
+use serde::{Deserialize, Serialize};
+use std::{fmt, str::FromStr, time::SystemTime};
+
+pub const SORTS: &[&str; 4] = &["time", "summary", "type", "priority"];
+pub const STATUSES: &[&str; 2] = &["done", "undone"];
+
+#[derive(Clone, Copy, PartialEq, Debug, Default, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Sort {
+    #[default]
+    Time,
+    Summary,
+    Type,
+    /// Highest first, then the ones without a priority.
+    Priority,
+}
+
+impl Sort {
+    pub fn next(self) -> Sort {
+        match self {
+            Sort::Time => Sort::Summary,
+            Sort::Summary => Sort::Type,
+            Sort::Type => Sort::Priority,
+            Sort::Priority => Sort::Time,
+        }
+    }
+    pub fn name(self) -> &'static str {
+        match self {
+            Sort::Time => "time",
+            Sort::Summary => "summary",
+            Sort::Type => "type",
+            Sort::Priority => "priority",
+        }
+    }
+}
+
+impl FromStr for Sort {
+    type Err = String;
+    fn from_str(s: &str) -> std::result::Result<Sort, String> {
+        match s {
+            "time" => Ok(Sort::Time),
+            "summary" => Ok(Sort::Summary),
+            "type" => Ok(Sort::Type),
+            "priority" => Ok(Sort::Priority),
+            _ => Err(format!(
+                "unknown sort `{}`, expect one of {}",
+                s,
+                SORTS.join(", ")
+            )),
+        }
+    }
+}
+
+/// Which items a listing shows and in what order, newest first by default.
+#[derive(Clone, Default, PartialEq, Debug, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Filter {
+    /// Types to keep, all of them when empty.
+    pub tags: Vec<String>,
+    /// `done` or `undone`, which keeps todos only.
+    pub status: Option<String>,
+    /// Bounds of the last change, as `parse_since` reads them; a plain
+    /// date as `until` includes that day.
+    pub since: Option<String>,
+    pub until: Option<String>,
+    pub has_url: bool,
+    pub has_detail: bool,
+    /// Text the items have to match, nothing when empty.
+    pub text: String,
+    /// A `--where` expression, as `query::parse` reads it.
+    pub expr: Option<String>,
+    pub limit: Option<usize>,
+    pub sort: Sort,
+    pub reverse: bool,
+    /// Whether archived items are kept.
+    pub archived: bool,
+}
+
+impl Filter {
+    /// The filter given by the listing flags of a subcommand.
+    pub fn from_matches(matches: &clap::ArgMatches) -> Result<Filter> {
+        let value = |name| matches.value_of(name).map(str::to_owned);
+        let filter = Filter {
+            tags: matches
+                .values_of("TAG")
+                .map_or(vec![], |tags| tags.map(str::to_owned).collect()),
+            status: value("STATUS"),
+            since: value("SINCE"),
+            until: value("UNTIL"),
+            has_url: matches.is_present("HAS_URL"),
+            has_detail: matches.is_present("HAS_DETAIL"),
+            text: value("MATCH").unwrap_or_default(),
+            expr: value("WHERE"),
+            limit: value("LIMIT")
+                .map(|n| n.parse())
+                .transpose()
+                .map_err(|_| Error::StringError("`--limit` expects a number".to_owned()))?,
+            sort: value("SORT").map_or(Ok(Sort::Time), |s| s.parse())?,
+            reverse: matches.is_present("REVERSE"),
+            archived: matches.is_present("ARCHIVED"),
+        };
+        filter.compile(SystemTime::now())?;
+        Ok(filter)
+    }
+
+    /// Whether anything beyond the type is asked for.
+    pub fn narrows(&self) -> bool {
+        self.status.is_some()
+            || self.since.is_some()
+            || self.until.is_some()
+            || self.has_url
+            || self.has_detail
+            || !self.text.is_empty()
+            || self.expr.is_some()
+            || self.limit.is_some()
+    }
+
+    /// The `since` and `until` times, `until` exclusive, and the parsed
+    /// expression.
+    fn compile(
+        &self,
+        now: SystemTime,
+    ) -> Result<(Option<SystemTime>, Option<SystemTime>, Option<Expr>)> {
+        let since = self
+            .since
+            .as_deref()
+            .map(|s| parse_since(s, now))
+            .transpose()?;
+        let until = self
+            .until
+            .as_deref()
+            .map(|s| match s.parse::<Date>() {
+                Ok(date) => Ok(date.add_days(1).to_time()),
+                Err(_) => parse_since(s, now),
+            })
+            .transpose()?;
+        let expr = self
+            .expr
+            .as_deref()
+            .map(|expr| query::parse(expr, now))
+            .transpose()?;
+        Ok((since, until, expr))
+    }
+
+    /// The items passing the filter, sorted with the pinned ones first and
+    /// cut to the limit.
+    pub fn apply(&self, mut items: Vec<ItemPair>) -> Result<Vec<ItemPair>> {
+        let (since, until, expr) = self.compile(SystemTime::now())?;
+        items.retain(|x| {
+            let item = &x.item;
+            let status = match self.status.as_deref() {
+                Some("done") => item.ty.status() == DONE,
+                Some(_) => item.ty.status() == UNDONE,
+                None => true,
+            };
+            (self.archived || !item.archived)
+                && (self.tags.is_empty() || self.tags.iter().any(|t| t == item.ty.desc()))
+                && status
+                && since.is_none_or(|since| item.time >= since)
+                && until.is_none_or(|until| item.time < until)
+                && (!self.has_url || !item.urls.is_empty())
+                && (!self.has_detail || item.detail.as_ref().is_some_and(|d| !d.is_empty()))
+                && (self.text.is_empty() || x.is_match(&self.text))
+                && expr.as_ref().is_none_or(|expr| expr.matches(item))
+        });
+        match self.sort {
+            Sort::Time => items.sort_by_key(|x| std::cmp::Reverse(x.item.time)),
+            Sort::Summary => items.sort_by(|a, b| a.item.summary.cmp(&b.item.summary)),
+            Sort::Type => items.sort_by(|a, b| {
+                a.item
+                    .ty
+                    .desc()
+                    .cmp(b.item.ty.desc())
+                    .then(b.item.time.cmp(&a.item.time))
+            }),
+            Sort::Priority => items.sort_by(|a, b| {
+                b.item
+                    .priority
+                    .cmp(&a.item.priority)
+                    .then(b.item.time.cmp(&a.item.time))
+            }),
+        }
+        if self.reverse {
+            items.reverse();
+        }
+        items.sort_by_key(|x| !x.item.pinned);
+        if let Some(limit) = self.limit {
+            items.truncate(limit);
+        }
+        Ok(items)
+    }
+
+    /// Like `apply` on the items with `ids` only, archived ones included
+    /// since they are asked for by id.
+    pub fn apply_ids(&self, mut items: Vec<ItemPair>, ids: &[&str]) -> Result<Vec<ItemPair>> {
+        items.retain(|x| ids.contains(&x.id.as_str()));
+        let filter = Filter {
+            archived: true,
+            ..self.clone()
+        };
+        filter.apply(items)
+    }
+}
+
+/// The flags giving this filter, or `all ideas`.
+impl fmt::Display for Filter {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let mut flags = vec![];
+        for tag in &self.tags {
+            flags.push(format!("--tag {}", tag));
+        }
+        let values = [
+            ("status", &self.status),
+            ("since", &self.since),
+            ("until", &self.until),
+        ];
+        for (name, value) in values {
+            if let Some(value) = value {
+                flags.push(format!("--{} {}", name, value));
+            }
+        }
+        if self.has_url {
+            flags.push("--has-url".to_owned());
+        }
+        if self.has_detail {
+            flags.push("--has-detail".to_owned());
+        }
+        if !self.text.is_empty() {
+            flags.push(format!("--match {:?}", self.text));
+        }
+        if let Some(expr) = &self.expr {
+            flags.push(format!("--where {:?}", expr));
+        }
+        if let Some(limit) = self.limit {
+            flags.push(format!("--limit {}", limit));
+        }
+        if self.sort != Sort::Time {
+            flags.push(format!("--sort {}", self.sort.name()));
+        }
+        if self.reverse {
+            flags.push("--reverse".to_owned());
+        }
+        if self.archived {
+            flags.push("--archived".to_owned());
+        }
+        match flags.len() {
+            0 => write!(f, "all ideas"),
+            _ => write!(f, "{}", flags.join(" ")),
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::item::{Item, Priority};
+    use std::time::Duration;
+    #[test]
+    fn test_basis() {
+        let now = SystemTime::now();
+        let pair = |id: &str, status, summary: &str, days: u64| {
+            let mut item = Item::new(Type::new(status), summary.to_owned());
+            item.time = now - Duration::from_secs(days * 24 * 60 * 60);
+            ItemPair {
+                id: id.to_owned(),
+                item,
+            }
+        };
+        let items = vec![
+            pair("1", DONE, "ship", 1),
+            pair("2", UNDONE, "test", 3),
+            pair("3", IDEA, "think", 10),
+        ];
+        let ids = |filter: &Filter| -> Vec<String> {
+            let items = filter.apply(items.clone()).unwrap();
+            items.into_iter().map(|x| x.id).collect()
+        };
+        assert_eq!(ids(&Filter::default()), ["1", "2", "3"]);
+        let recent = Filter {
+            since: Some("7d".to_owned()),
+            sort: Sort::Summary,
+            reverse: true,
+            ..Filter::default()
+        };
+        assert_eq!(ids(&recent), ["2", "1"]);
+        let undone = Filter {
+            status: Some("undone".to_owned()),
+            ..Filter::default()
+        };
+        assert_eq!(ids(&undone), ["2"]);
+        let limited = Filter {
+            limit: Some(1),
+            has_detail: true,
+            ..Filter::default()
+        };
+        assert!(ids(&limited).is_empty());
+        assert!(limited.narrows());
+        assert!("size".parse::<Sort>().is_err());
+        assert_eq!(recent.to_string(), "--since 7d --sort summary --reverse");
+        let saved: Filter = serde_json::from_str(r#"{"sort": "type"}"#).unwrap();
+        assert_eq!(saved.sort, Sort::Type);
+
+        let mut flagged = items.clone();
+        flagged[0].item.archived = true;
+        flagged[2].item.pinned = true;
+        let flagged_ids = |filter: &Filter| -> Vec<String> {
+            let items = filter.apply(flagged.clone()).unwrap();
+            items.into_iter().map(|x| x.id).collect()
+        };
+        assert_eq!(flagged_ids(&Filter::default()), ["3", "2"]);
+        let archived = Filter {
+            archived: true,
+            ..Filter::default()
+        };
+        assert_eq!(flagged_ids(&archived), ["3", "1", "2"]);
+        let picked = Filter::default().apply_ids(flagged.clone(), &["1", "2"]);
+        let picked: Vec<_> = picked.unwrap().into_iter().map(|x| x.id).collect();
+        assert_eq!(picked, ["1", "2"]);
+
+        let mut ranked = items.clone();
+        ranked[1].item.priority = Some(Priority::High);
+        ranked[2].item.priority = Some(Priority::Low);
+        let priority = Filter {
+            sort: "priority".parse().unwrap(),
+            ..Filter::default()
+        };
+        let ranked: Vec<_> = priority.apply(ranked).unwrap();
+        let ranked: Vec<_> = ranked.into_iter().map(|x| x.id).collect();
+        assert_eq!(ranked, ["2", "3", "1"]);
+    }
+}
@@ -1,29 +1,62 @@
 mod icore;
 mod ieditor;
+mod ievent;
+mod ikeys;
+mod iline;
+mod itheme;
+mod itui;
 mod iview;
+mod iwidth;
 
+use crate::clipboard::Clipboard;
 use crate::error::*;
+use crate::filter::Filter;
 use crate::item::*;
+use crate::recur::RECUR_HINT;
+use crate::reference::{Reference, REFERENCE_HINT};
 use crate::status::*;
 use crate::store::ItemPair;
 use icore::Core;
 use iview::View;
 use std::{
     fmt::{self, Debug, Display, Formatter},
-    io::{StdinLock, StdoutLock},
+    io::{BufRead, StdinLock, StdoutLock, Write},
 };
 
+const CHECKLIST_HINT: &str = "[option: `;` separated, `[x] ` marks done]";
+const LABEL_HINT: &str = "[option: `,` separated, Tab completes]";
+
+/// Loads the known labels and urls.
+type Known = Box<dyn FnOnce() -> Result<(Vec<String>, Vec<String>)>>;
+
 pub type IC<'a> = Core<StdinLock<'a>, StdoutLock<'a>>;
-pub use icore::Searchable;
+pub type II<'a> = Interaction<StdinLock<'a>, StdoutLock<'a>>;
+pub use icore::{PickState, Searchable};
+pub use ievent::{plain_terminal, EventSource, Script};
+pub use ikeys::KeyMap;
+pub use iline::History;
+pub use itheme::{color_enabled, Theme};
+pub use itui::{Tui, TuiOp};
+pub use iview::ViewOp;
 
 #[derive(Clone, Debug)]
 pub enum Op {
     Cancel,
     Update,
     Retrieve,
+    Check,
     Delete,
 }
 
+#[derive(Clone, Debug)]
+pub enum BulkOp {
+    Done,
+    Undone,
+    Retag,
+    Delete,
+    Cancel,
+}
+
 #[derive(Clone, Debug)]
 pub enum Is {
     Yes,
@@ -38,7 +71,7 @@ macro_rules! derive_enum {
             }
         }
         impl Searchable for $id {
-            fn is_match(&self, pat: &String) -> bool {
+            fn is_match(&self, pat: &str) -> bool {
                 self.to_string().contains(pat)
             }
         }
@@ -46,21 +79,102 @@ macro_rules! derive_enum {
 }
 
 derive_enum!(Op);
+derive_enum!(BulkOp);
 derive_enum!(Is);
 
-pub struct Interaction<'a> {
-    core: IC<'a>,
+pub struct Interaction<R, W> {
+    core: Core<R, W>,
     view: View,
+    known: Option<Known>,
+    labels: Vec<String>,
+    urls: Vec<String>,
 }
 
-impl<'a> Interaction<'a> {
-    pub fn new(core: IC<'a>) -> Self {
+impl<R, W> Interaction<R, W>
+where
+    R: BufRead,
+    W: Write,
+{
+    pub fn new(core: Core<R, W>) -> Self {
         Interaction {
             core,
             view: View::new(),
+            known: None,
+            labels: vec![],
+            urls: vec![],
+        }
+    }
+
+    /// Known labels and urls, offered by Tab in their prompts. `known` only
+    /// runs once such a prompt shows up.
+    pub fn completions(
+        &mut self,
+        known: impl FnOnce() -> Result<(Vec<String>, Vec<String>)> + 'static,
+    ) {
+        self.known = Some(Box::new(known));
+    }
+
+    fn load_completions(&mut self) -> Result<()> {
+        if let Some(known) = self.known.take() {
+            let (labels, urls) = known()?;
+            self.labels = labels;
+            self.urls = urls;
+        }
+        Ok(())
+    }
+
+    /// Answers typed so far, to be saved for the next run, `None` when no
+    /// prompt ran.
+    pub fn take_history(&mut self) -> Option<History> {
+        self.core.take_history()
+    }
+
+    /// Where pickers, prompts, the viewer and the tui read events from, the
+    /// terminal by default.
+    pub fn events(&mut self, events: Box<dyn EventSource>) -> &mut Self {
+        self.core.events(events);
+        self
+    }
+
+    /// Turns mouse capture on or off for pickers, the viewer and the tui.
+    pub fn mouse(&mut self, mouse: bool) -> &mut Self {
+        self.core.mouse(mouse);
+        self.view.set_mouse(mouse);
+        self
+    }
+
+    /// Where the copy action of pickers, the viewer and the tui puts text.
+    pub fn clipboard(&mut self, clipboard: Clipboard) -> &mut Self {
+        self.core.clipboard(clipboard.clone());
+        self.view.set_clipboard(clipboard);
+        self
+    }
+
+    pub fn theme(&mut self, theme: Theme) -> &mut Self {
+        self.core.theme(theme.clone());
+        self.view.set_theme(theme);
+        self
+    }
+
+    /// Prints `markdown` with the skin of the viewer on a terminal, and as
+    /// is when redirected.
+    pub fn print_markdown(&self, markdown: &str) {
+        if atty::is(atty::Stream::Stdout) {
+            print!("{}", self.view.skin().term_text(markdown));
+        } else {
+            print!("{}", markdown);
         }
     }
 
+    pub fn tui(&self) -> Tui {
+        Tui::new(self.view.clone())
+    }
+
+    /// Runs `tui` on the events of the pickers, see `Tui::run`.
+    pub fn run_tui(&mut self, tui: &mut Tui, items: Vec<ItemPair>) -> Result<TuiOp> {
+        tui.run(items, self.core.event_source())
+    }
+
     pub fn confirm_again(&mut self) -> Result<Is> {
         self.core.question("? Do you confirm", "<required>")?;
         let is = self
@@ -69,37 +183,147 @@ impl<'a> Interaction<'a> {
         Ok(is)
     }
 
-    pub fn curd(&mut self) -> Result<Op> {
+    pub fn curd(&mut self, item: &Item) -> Result<Op> {
+        self.core
+            .question("? Please select your operator", "<required>")?;
+        let mut ops = vec![Op::Retrieve, Op::Update];
+        if !item.subtasks.is_empty() {
+            ops.push(Op::Check);
+        }
+        ops.extend(vec![Op::Delete, Op::Cancel]);
+        let op = self
+            .core
+            .read_input_from(ops, icore::Direction::Horizontal)?;
+        Ok(op)
+    }
+
+    pub fn bulk_op(&mut self) -> Result<BulkOp> {
         self.core
             .question("? Please select your operator", "<required>")?;
         let op = self.core.read_input_from(
-            vec![Op::Retrieve, Op::Update, Op::Delete, Op::Cancel],
+            vec![
+                BulkOp::Done,
+                BulkOp::Undone,
+                BulkOp::Retag,
+                BulkOp::Delete,
+                BulkOp::Cancel,
+            ],
             icore::Direction::Horizontal,
         )?;
         Ok(op)
     }
 
-    pub fn view_items(&mut self, items: Vec<ItemPair>) -> Result<ItemPair> {
+    pub fn select_type(&mut self) -> Result<Type> {
+        self.core
+            .question("? Please select the type", "<required>")?;
+        self.core.read_input_from(
+            vec![Type::new(IDEA), Type::new(TIPS), Type::new(UNDONE)],
+            icore::Direction::Horizontal,
+        )
+    }
+
+    /// Picks one of the saved `queries`, returning its index, or `None`
+    /// for all ideas.
+    pub fn select_query(&mut self, queries: &[(String, Filter)]) -> Result<Option<usize>> {
+        self.core
+            .question("? Please select the view", "<required>")?;
+        let mut views = vec!["all ideas".to_owned()];
+        views.extend(
+            queries
+                .iter()
+                .map(|(name, filter)| format!("@{}  {}", name, filter)),
+        );
+        let view = self
+            .core
+            .read_index_from(views, icore::Direction::Vertical)?;
+        Ok(view.checked_sub(1))
+    }
+
+    /// Picks a subtask to toggle, returning its 1-based index.
+    pub fn check_subtask(&mut self, item: &Item) -> Result<usize> {
+        self.core
+            .question("? Please select the subtask to toggle", "<required>")?;
+        let tasks: Vec<_> = item
+            .subtasks
+            .iter()
+            .enumerate()
+            .map(|(i, t)| format!("{}. {}", i + 1, t))
+            .collect();
+        let task = self
+            .core
+            .read_input_from(tasks.clone(), icore::Direction::Vertical)?;
+        Ok(tasks.iter().position(|t| t == &task).unwrap() + 1)
+    }
+
+    /// Picks an item, `state.flag` tells whether it was picked to be pinned
+    /// or archived instead.
+    pub fn view_items<T>(&mut self, items: Vec<T>, state: &mut PickState) -> Result<T>
+    where
+        T: Display + Clone + Searchable,
+    {
         if items.is_empty() {
             return Err(Error::StringError("Nothing here".to_string()));
         }
         self.core
             .question("? Please select the idea you want to operate", "<required>")?;
-        let item = self
-            .core
-            .read_input_from(items, icore::Direction::Vertical)?;
-        Ok(item)
+        self.core
+            .read_input_state(items, icore::Direction::Vertical, state)
     }
 
-    pub fn view_item(&mut self, item: Item) -> Result<()> {
-        self.view.run(item);
-        Ok(())
+    /// Reopens the list where `state` left it, `None` once the user leaves.
+    pub fn view_items_resume<T>(
+        &mut self,
+        items: Vec<T>,
+        state: &mut PickState,
+    ) -> Result<Option<T>>
+    where
+        T: Display + Clone + Searchable,
+    {
+        if items.is_empty() {
+            return Ok(None);
+        }
+        self.core.question(
+            "? Please select the idea you want to operate",
+            "<Esc to quit>",
+        )?;
+        self.core
+            .read_input_resume(items, icore::Direction::Vertical, state)
+    }
+
+    pub fn view_items_multi<T>(&mut self, items: Vec<T>) -> Result<Vec<T>>
+    where
+        T: Display + Clone + Searchable,
+    {
+        if items.is_empty() {
+            return Err(Error::StringError("Nothing here".to_string()));
+        }
+        self.core.question(
+            "? Please select the ideas you want to operate",
+            "<required>",
+        )?;
+        self.core
+            .read_inputs_from(items, icore::Direction::Vertical)
+    }
+
+    /// Pages through `item`, or just prints it when the screen can't be
+    /// drawn on.
+    pub fn view_item(&mut self, item: Item, related: Vec<(String, ItemPair)>) -> Result<ViewOp> {
+        if self.core.is_plain() {
+            let page = self.view.page(&item, &related);
+            self.core.print(&page.render(self.view.skin(), None))?;
+            return Ok(ViewOp::Quit);
+        }
+        self.view.run(item, related, self.core.event_source())
     }
 
     pub fn update_item(&mut self, mut item: Item) -> Result<Item> {
         let mut status = vec![item.ty.status()];
-        item.ty.next_status().map(|s| status.push(s));
-        item.ty.last_status().map(|s| status.push(s));
+        if let Some(s) = item.ty.next_status() {
+            status.push(s);
+        }
+        if let Some(s) = item.ty.last_status() {
+            status.push(s);
+        }
         if status.len() > 1 {
             self.core
                 .question("? Please select the status", "<required>")?;
@@ -113,57 +337,168 @@ impl<'a> Interaction<'a> {
             .question("? Please enter the summary", "[option]")?;
         item.summary = self
             .core
-            .read_input_with(false, Some(item.summary))?
-            .unwrap();
+            .read_input_with("summary", false, Some(item.summary))?
+            .ok_or_else(|| Error::StringError("the field `idea` is required!".to_string()))?;
         self.core
             .question("? Please enter the detail", "[option]")?;
-        item.detail = self.core.read_input_with(true, item.detail)?;
-        self.core
-            .question("? Please enter the related url", "[option]")?;
-        item.url = self.core.read_input_with(true, item.url)?;
+        item.detail = self.core.read_text("detail", true, item.detail)?;
+        self.read_references(&mut item)?;
+        if item.ty.desc() == TTODO {
+            self.core
+                .question("? Please enter the checklist", CHECKLIST_HINT)?;
+            let checklist = self
+                .core
+                .read_input_with("checklist", true, item.checklist())?;
+            item.set_checklist(checklist);
+            self.read_schedule(&mut item)?;
+        }
         item.time = std::time::SystemTime::now();
         Ok(item)
     }
 
-    pub fn fill_item(&mut self, skip: bool) -> Result<Item> {
+    fn read_schedule(&mut self, item: &mut Item) -> Result<()> {
         self.core
-            .question("? Please select the type", "<required>")?;
-        let ty = self.core.read_input_from(
-            vec![Type::new(IDEA), Type::new(TIPS), Type::new(UNDONE)],
-            icore::Direction::Horizontal,
-        )?;
+            .question("? Please enter the due date", "[option: YYYY-MM-DD]")?;
+        item.due = self
+            .core
+            .read_input_with("due", false, item.due.map(|d| d.to_string()))?
+            .map(|s| s.parse())
+            .transpose()?;
+        self.core
+            .question("? Please enter the recurrence", RECUR_HINT)?;
+        item.recur = self
+            .core
+            .read_input_with("recur", false, item.recur.as_ref().map(|r| r.to_string()))?
+            .map(|s| s.parse())
+            .transpose()?;
+        Ok(())
+    }
+
+    fn read_references(&mut self, item: &mut Item) -> Result<()> {
+        self.load_completions()?;
+        self.core
+            .question("? Please enter the related urls", REFERENCE_HINT)?;
+        let urls =
+            self.core
+                .read_line("urls", true, Reference::format_list(&item.urls), &self.urls)?;
+        item.urls = Reference::parse_list(urls.as_deref().unwrap_or(""))?;
+        self.core
+            .question("? Please enter the labels", LABEL_HINT)?;
+        let labels = self
+            .core
+            .read_line("labels", false, item.label_list(), &self.labels)?;
+        item.set_labels(labels);
+        Ok(())
+    }
+
+    pub fn fill_item(&mut self, skip: bool) -> Result<Item> {
+        let ty = self.select_type()?;
 
         self.core
             .question("? Please enter the summary", "<required>")?;
         let summary = self
             .core
-            .read_input(false)
-            .map_err(|e| Error::from(e))
+            .read_input("summary", false)
             .and_then(|idea| match idea {
                 Some(idea) => Ok(idea),
                 _ => Err(Error::StringError(
                     "the field `idea` is required!".to_string(),
                 )),
             })?;
-        let detail;
-        let url;
+        let mut item = Item::new(ty, summary);
         if !skip {
             self.core
                 .question("? Please enter the detail", "[option]")?;
-            detail = self.core.read_input(true)?;
+            item.detail = self.core.read_text("detail", true, None)?;
+            self.read_references(&mut item)?;
+        }
+        if !skip && item.ty.status() == UNDONE {
             self.core
-                .question("? Please enter the related url", "[option]")?;
-            url = self.core.read_input(true)?;
-        } else {
-            detail = None;
-            url = None;
-        }
-        Ok(Item {
-            ty,
-            summary,
-            detail,
-            url,
-            time: std::time::SystemTime::now(),
-        })
+                .question("? Please enter the checklist", CHECKLIST_HINT)?;
+            let checklist = self.core.read_input("checklist", true)?;
+            item.set_checklist(checklist);
+            self.read_schedule(&mut item)?;
+        }
+        Ok(item)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::ievent::Script;
+    use super::*;
+    use crossterm::event::{Event, KeyCode, KeyEvent, KeyModifiers};
+    use std::io::{empty, sink, Empty, Sink};
+
+    fn interaction(events: Vec<Event>) -> Interaction<Empty, Sink> {
+        let mut core = Core::new(empty(), sink());
+        core.events(Box::new(Script::new(events)));
+        Interaction::new(core)
+    }
+
+    fn key(code: KeyCode) -> Event {
+        Event::Key(code.into())
+    }
+
+    #[test]
+    fn test_basis() {
+        let mut events = vec![
+            key(KeyCode::Right),
+            key(KeyCode::Right),
+            key(KeyCode::Enter),
+        ];
+        events.extend(Script::keys(
+            "write tests\nfirst line\n\nci, rust\nunit; [x] e2e\n2030-01-01\nweekly\n",
+        ));
+        let item = interaction(events).fill_item(false).unwrap();
+        assert_eq!(item.ty.status(), UNDONE);
+        assert_eq!(item.summary, "write tests");
+        assert_eq!(item.detail.as_deref(), Some("first line"));
+        assert!(item.urls.is_empty());
+        assert_eq!(item.labels, ["ci", "rust"]);
+        assert_eq!(item.progress(), Some((1, 2)));
+        assert_eq!(item.due.unwrap().to_string(), "2030-01-01");
+        assert!(item.recur.is_some());
+
+        let mut events = vec![key(KeyCode::Right), key(KeyCode::Enter)];
+        events.extend(Script::keys(" well\n\n\n, docs\n\n\n"));
+        events.push(Event::Key(KeyEvent::new(
+            KeyCode::Char('u'),
+            KeyModifiers::CONTROL,
+        )));
+        events.push(key(KeyCode::Enter));
+        let item = interaction(events).update_item(item).unwrap();
+        assert_eq!(item.ty.status(), DONE);
+        assert_eq!(item.summary, "write tests well");
+        assert_eq!(item.detail.as_deref(), Some("first line"));
+        assert_eq!(item.labels, ["ci", "rust", "docs"]);
+        assert_eq!(item.progress(), Some((1, 2)));
+        assert!(item.recur.is_none());
+
+        let items = vec!["alpha", "beta", "gamma"];
+        let mut events = Script::keys("ga");
+        events.push(key(KeyCode::Enter));
+        assert_eq!(
+            interaction(events)
+                .view_items(items.clone(), &mut PickState::default())
+                .unwrap(),
+            "gamma"
+        );
+        let events = vec![key(KeyCode::Down), key(KeyCode::Enter)];
+        let mut state = PickState::default();
+        assert_eq!(
+            interaction(events).view_items(items, &mut state).unwrap(),
+            "beta"
+        );
+        assert_eq!(state.flag, None);
+
+        let items = vec![Item::new(Type::new(IDEA), "pin me".to_owned())];
+        let events = vec![Event::Key(KeyEvent::new(
+            KeyCode::Char('p'),
+            KeyModifiers::ALT,
+        ))];
+        let item = interaction(events).view_items(items, &mut state).unwrap();
+        assert_eq!(item.summary, "pin me");
+        assert_eq!(state.flag, Some(Flag::Pinned));
     }
 }
@@ -1,11 +1,35 @@
 use super::ieditor::*;
-use crate::{error::Error, Result};
-use crossterm::{cursor, event, execute, style, style::Colorize, terminal};
+use super::ievent::{EventSource, Terminal};
+use super::ikeys::{Action, KeyMap};
+use super::iline::{History, LineBuffer};
+use super::itheme::Theme;
+use super::iwidth::{truncate, width};
+use crate::clipboard::{Clip, Clipboard, CLIP_HELP};
+use crate::{error::Error, item::Flag, status::Icons, Result};
+use crossterm::{cursor, event, execute, queue, style, terminal};
 use defer::defer;
 use std::io::{BufRead, Write};
+use std::time::{Duration, Instant};
+
+const DOUBLE_CLICK: Duration = Duration::from_millis(400);
 
 pub trait Searchable {
-    fn is_match(&self, pat: &String) -> bool;
+    fn is_match(&self, pat: &str) -> bool;
+    /// The text the copy action takes from the item, if it has `part`.
+    fn clip(&self, _part: Clip) -> Option<String> {
+        None
+    }
+    /// Whether the pin and archive actions apply to the item.
+    fn flaggable(&self) -> bool {
+        false
+    }
+    /// The item as pickers list it, statuses drawn with `icons`.
+    fn line(&self, _icons: Icons) -> String
+    where
+        Self: std::fmt::Display,
+    {
+        self.to_string()
+    }
 }
 
 pub enum Direction {
@@ -14,11 +38,30 @@ pub enum Direction {
     Horizontal,
 }
 
+/// Where a picker was left, so it can be reopened in the same place.
+#[derive(Default, Clone)]
+pub struct PickState {
+    pub cur: i32,
+    pub start: i32,
+    pub search: String,
+    /// The flag to toggle on the picked item, when it was picked with the
+    /// pin or archive action rather than confirmed.
+    pub flag: Option<Flag>,
+}
+
 pub struct Core<R, W> {
     reader: R,
     writer: W,
     prompt: &'static str,
     visiable_nums: usize,
+    keymap: KeyMap,
+    history: History,
+    prompted: bool,
+    mouse: bool,
+    theme: Theme,
+    events: Box<dyn EventSource>,
+    plain: bool,
+    clipboard: Clipboard,
 }
 
 impl<R, W> Core<R, W>
@@ -32,6 +75,14 @@ where
             writer,
             prompt: ">>>",
             visiable_nums: 5,
+            keymap: KeyMap::default(),
+            history: History::default(),
+            prompted: false,
+            mouse: false,
+            theme: Theme::default(),
+            events: Box::new(Terminal),
+            plain: false,
+            clipboard: Clipboard::default(),
         }
     }
 
@@ -47,36 +98,168 @@ where
         self
     }
 
+    pub fn keymap(&mut self, keymap: KeyMap) -> &mut Self {
+        self.keymap = keymap;
+        self
+    }
+
+    pub fn mouse(&mut self, mouse: bool) -> &mut Self {
+        self.mouse = mouse;
+        self
+    }
+
+    pub fn theme(&mut self, theme: Theme) -> &mut Self {
+        self.theme = theme;
+        self
+    }
+
+    pub fn history(&mut self, history: History) -> &mut Self {
+        self.history = history;
+        self
+    }
+
+    pub fn clipboard(&mut self, clipboard: Clipboard) -> &mut Self {
+        self.clipboard = clipboard;
+        self
+    }
+
+    /// Where events come from instead of the terminal, see `Script`.
+    pub fn events(&mut self, events: Box<dyn EventSource>) -> &mut Self {
+        self.events = events;
+        self
+    }
+
+    /// The events the tui and the viewer read too.
+    pub fn event_source(&mut self) -> &mut dyn EventSource {
+        self.events.as_mut()
+    }
+
+    /// Numbered menus and plain line prompts instead of drawing on the
+    /// screen, see `plain_terminal`.
+    pub fn plain(&mut self, plain: bool) -> &mut Self {
+        self.plain = plain;
+        self
+    }
+
+    pub fn is_plain(&self) -> bool {
+        self.plain
+    }
+
+    pub fn print(&mut self, text: &str) -> Result<()> {
+        writeln!(self.writer, "{}", text)?;
+        self.writer.flush()?;
+        Ok(())
+    }
+
     pub fn question(&mut self, question: &'static str, tips: &'static str) -> Result<()> {
         writeln!(
             self.writer,
             "{} {} {}",
-            self.prompt.green(),
-            question.white(),
-            tips.blue()
+            self.theme.prompt.apply(self.prompt),
+            self.theme.question.apply(question),
+            self.theme.tips.apply(tips)
         )?;
-        write!(self.writer, "❯ ")?;
+        if !self.plain {
+            write!(self.writer, "{}", marks(self.theme.icons).0)?;
+        }
         self.writer.flush()?;
         Ok(())
     }
 
     pub fn read_input_from<T>(&mut self, list: Vec<T>, direction: Direction) -> Result<T>
+    where
+        T: std::fmt::Display + Clone + Searchable,
+    {
+        let mut state = PickState::default();
+        self.read_input_state(list, direction, &mut state)
+    }
+
+    /// Like `read_input_from`, but returns where the picked item is in `list`.
+    pub fn read_index_from<T>(&mut self, list: Vec<T>, direction: Direction) -> Result<usize>
+    where
+        T: std::fmt::Display + Clone + Searchable,
+    {
+        let list = list.into_iter().enumerate().map(|(i, x)| Indexed(i, x));
+        Ok(self.read_input_from(list.collect(), direction)?.0)
+    }
+
+    /// Like `read_input_from`, but starts from and saves to `state`.
+    pub fn read_input_state<T>(
+        &mut self,
+        list: Vec<T>,
+        direction: Direction,
+        state: &mut PickState,
+    ) -> Result<T>
+    where
+        T: std::fmt::Display + Clone + Searchable,
+    {
+        Ok(self
+            .pick(list, direction, false, false, state)?
+            .unwrap()
+            .remove(0))
+    }
+
+    /// Like `read_input_from`, but resumes from and saves to `state`, and
+    /// returns `None` when the user leaves with Esc.
+    pub fn read_input_resume<T>(
+        &mut self,
+        list: Vec<T>,
+        direction: Direction,
+        state: &mut PickState,
+    ) -> Result<Option<T>>
+    where
+        T: std::fmt::Display + Clone + Searchable,
+    {
+        Ok(self
+            .pick(list, direction, false, true, state)?
+            .map(|mut chosen| chosen.remove(0)))
+    }
+
+    /// Like `read_input_from`, but Space toggles items and Enter confirms all
+    /// toggled ones (or the current one if nothing is toggled).
+    pub fn read_inputs_from<T>(&mut self, list: Vec<T>, direction: Direction) -> Result<Vec<T>>
+    where
+        T: std::fmt::Display + Clone + Searchable,
+    {
+        let mut state = PickState::default();
+        Ok(self
+            .pick(list, direction, true, false, &mut state)?
+            .unwrap())
+    }
+
+    fn pick<T>(
+        &mut self,
+        list: Vec<T>,
+        direction: Direction,
+        multi: bool,
+        cancelable: bool,
+        state: &mut PickState,
+    ) -> Result<Option<Vec<T>>>
     where
         T: std::fmt::Display + Clone + Searchable,
     {
         assert!(!list.is_empty());
-        let mut cur: i32 = 0;
-        let mut start: i32 = 0;
-        let mut visiable_nums = std::cmp::min(self.visiable_nums, list.len()) as i32;
-        let visiable_lines = match direction {
+        state.flag = None;
+        if self.plain {
+            return self.pick_plain(list, multi, cancelable);
+        }
+        let mut selected = vec![false; list.len()];
+        let mut cur: i32 = state.cur;
+        let mut start: i32 = state.start;
+        // leave a row for the question and one for the help
+        let most = self.visiable_nums;
+        let fit = |len: usize, h: u16| {
+            let rows = std::cmp::max(1, h.saturating_sub(2)) as usize;
+            std::cmp::min(std::cmp::min(most, len), rows) as i32
+        };
+        let mut visiable_nums = fit(list.len(), self.events.size()?.1);
+        let lines = |visiable_nums: i32| match direction {
             Direction::Vertical => visiable_nums,
             Direction::Horizontal => 1,
         };
-        let mut list_str = vec![];
+        let icons = self.theme.icons;
+        let list_str: Vec<_> = list.iter().map(|item| item.line(icons)).collect();
         let mut list_idx: Vec<_> = (0..list.len()).collect();
-        for item in &list {
-            list_str.push(format!("❯ {:8}\t", item));
-        }
         let move_cursor = |cursor: &mut i32, step: i32, max: usize| {
             if max == 0 {
                 *cursor = 0;
@@ -86,25 +269,39 @@ where
             while *cursor < 0i32 {
                 *cursor += max as i32;
             }
-            *cursor = *cursor % (max as i32);
+            *cursor %= max as i32;
         };
-        for _ in 0..(visiable_lines + 1) {
-            writeln!(self.writer, "")?;
+        for _ in 0..(lines(visiable_nums) + 1) {
+            writeln!(self.writer)?;
         }
         self.writer.flush()?;
-        let (begin_x, end_y) = cursor::position()?;
-        let begin_y = end_y - 1 - visiable_lines as u16;
-        let mut search_buf = String::with_capacity(16);
-        let mut search = false;
-        let mut dirty = false;
+        let (begin_x, end_y) = self.events.position()?;
+        let mut begin_y = end_y.saturating_sub(1 + lines(visiable_nums) as u16);
+        let mut search_buf = state.search.clone();
+        let mut search = !search_buf.is_empty();
+        let mut dirty = search;
+        let mut last_click: Option<(usize, Instant)> = None;
+        // waiting for the part to copy, or what the last copy did
+        let mut copying = false;
+        let mut notice = String::new();
         loop {
             if dirty {
                 list_idx = (0..list.len())
                     .filter(|i| list[*i].is_match(&search_buf))
                     .collect();
-                visiable_nums = std::cmp::min(self.visiable_nums, list_idx.len()) as i32;
+                visiable_nums = fit(list_idx.len(), self.events.size()?.1);
                 dirty = false;
             }
+            let (w, h) = self.events.size()?;
+            begin_y = std::cmp::min(begin_y, h.saturating_sub(1 + lines(visiable_nums) as u16));
+            let len = list_idx.len() as i32;
+            cur = std::cmp::max(0, std::cmp::min(cur, len - 1));
+            if start >= len {
+                start = cur;
+            }
+            state.cur = cur;
+            state.start = start;
+            state.search = search_buf.clone();
             execute!(
                 self.writer,
                 cursor::MoveTo(begin_x, begin_y),
@@ -114,20 +311,37 @@ where
             let mut scroll = false;
             let mut cur_y = begin_y;
             let mut cur_x = begin_x;
+            // (row, first column, last column, display index) of each item
+            let mut hits = vec![];
+            let mut x = begin_x;
             for i in 0..visiable_nums {
                 let display_idx = (i + start) as usize % list_idx.len();
                 let idx = list_idx[display_idx];
-                let item = &list_str[idx as usize];
+                let (pointer, checked) = marks(icons);
+                let mark = if selected[idx] { checked } else { pointer };
+                let room = (w as usize).saturating_sub(begin_x as usize + width(mark) + 1);
+                let item = format!("{}{:8}\t", mark, truncate(&list_str[idx], room));
+                match direction {
+                    Direction::Vertical => {
+                        hits.push((begin_y + i as u16, 0, u16::MAX, display_idx));
+                    }
+                    Direction::Horizontal => {
+                        let end = x + width(item.trim_end()) as u16;
+                        hits.push((begin_y, x, end, display_idx));
+                        x = (end / 8 + 1) * 8;
+                    }
+                }
                 if cur == display_idx as i32 {
                     if i == (visiable_nums - 1) / 2 {
                         scroll = true;
                     }
-                    let (new_x, new_y) = cursor::position()?;
+                    let (new_x, new_y) = self.events.position()?;
                     cur_x = new_x;
                     cur_y = new_y;
-                    execute!(self.writer, style::Print(item[..].black().on_yellow()),)?;
+                    let style = self.theme.selected;
+                    execute!(self.writer, style::Print(style.apply(item)))?;
                 } else {
-                    execute!(self.writer, style::Print(item),)?;
+                    execute!(self.writer, style::Print(item))?;
                 }
                 match direction {
                     Direction::Vertical => execute!(self.writer, cursor::MoveToNextLine(1))?,
@@ -138,81 +352,239 @@ where
                 }
             }
             if search {
+                let style = self.theme.search;
                 execute!(
                     self.writer,
-                    style::Print(format!("Search: {}", search_buf).dark_blue()),
+                    style::Print(style.apply(format!("Search: {}", search_buf))),
                 )?;
             } else {
+                let help = if copying {
+                    CLIP_HELP
+                } else if !notice.is_empty() {
+                    &notice[..]
+                } else if multi {
+                    "(*Move [ArrowKey/Tab], *Toggle [Space], *Confirm [Enter])"
+                } else {
+                    "(*Move [ArrowKey/Tab], *Confirm [Enter])"
+                };
+                let style = self.theme.help;
                 execute!(
                     self.writer,
-                    style::Print("(*Move [ArrowKey/Tab], *Confirm [Enter])".dark_grey()),
+                    style::Print(style.apply(help)),
                     cursor::MoveTo(cur_x, cur_y)
                 )?;
             }
 
-            terminal::enable_raw_mode()?;
+            let tty = self.events.is_terminal();
+            let mouse = self.mouse && tty;
+            if tty {
+                terminal::enable_raw_mode()?;
+            }
+            if mouse {
+                execute!(self.writer, event::EnableMouseCapture)?;
+            }
             let reset = defer(|| {
-                terminal::disable_raw_mode().unwrap();
+                if mouse {
+                    execute!(std::io::stdout(), event::DisableMouseCapture).unwrap();
+                }
+                if tty {
+                    terminal::disable_raw_mode().unwrap();
+                }
             });
 
             loop {
-                match event::read()? {
-                    event::Event::Key(event) => match event.code {
-                        event::KeyCode::Up | event::KeyCode::BackTab | event::KeyCode::Left => {
-                            search = false;
-                            move_cursor(&mut cur, -1, list_idx.len());
-                            if scroll || at_start {
-                                move_cursor(&mut start, -1, list_idx.len());
+                let (action, key) = match self.events.read()? {
+                    event::Event::Key(key) => (self.keymap.action(&key, search), Some(key)),
+                    event::Event::Mouse(m) => match m.kind {
+                        event::MouseEventKind::ScrollUp => (Some(Action::Up), None),
+                        event::MouseEventKind::ScrollDown => (Some(Action::Down), None),
+                        event::MouseEventKind::Down(event::MouseButton::Left) => {
+                            let hit = hits.iter().find(|(row, from, to, _)| {
+                                *row == m.row && (*from..=*to).contains(&m.column)
+                            });
+                            let idx = match hit {
+                                Some(&(.., idx)) => idx,
+                                None => continue,
+                            };
+                            let double = last_click.is_some_and(|(last, at)| {
+                                last == idx && at.elapsed() < DOUBLE_CLICK
+                            });
+                            last_click = Some((idx, Instant::now()));
+                            cur = idx as i32;
+                            if !double {
+                                break;
                             }
-                            break;
+                            (Some(Action::Confirm), None)
                         }
-                        event::KeyCode::Down | event::KeyCode::Tab | event::KeyCode::Right => {
-                            search = false;
-                            move_cursor(&mut cur, 1, list_idx.len());
-                            if scroll {
-                                move_cursor(&mut start, 1, list_idx.len());
-                            }
-                            break;
+                        _ => continue,
+                    },
+                    event::Event::Resize(..) => {
+                        execute!(
+                            self.writer,
+                            cursor::MoveTo(begin_x, begin_y),
+                            terminal::Clear(terminal::ClearType::FromCursorDown)
+                        )?;
+                        dirty = true;
+                        break;
+                    }
+                };
+                notice.clear();
+                if copying {
+                    copying = false;
+                    let part = match key.map(|key| key.code) {
+                        Some(event::KeyCode::Char(c)) => Clip::from_key(c),
+                        _ => None,
+                    };
+                    if let Some(part) = part {
+                        let item = &list[list_idx[cur as usize]];
+                        notice = self.clipboard.copy_part(part, item.clip(part));
+                    }
+                    break;
+                }
+                let action = match action {
+                    Some(Action::Toggle) if !multi => None,
+                    Some(Action::Pin) | Some(Action::Archive) if multi => None,
+                    action => action,
+                };
+                let len = list_idx.len() as i32;
+                match action {
+                    Some(Action::Up) => {
+                        search = false;
+                        move_cursor(&mut cur, -1, list_idx.len());
+                        if scroll || at_start {
+                            move_cursor(&mut start, -1, list_idx.len());
+                        }
+                        break;
+                    }
+                    Some(Action::Down) => {
+                        search = false;
+                        move_cursor(&mut cur, 1, list_idx.len());
+                        if scroll {
+                            move_cursor(&mut start, 1, list_idx.len());
+                        }
+                        break;
+                    }
+                    Some(jump @ Action::PageUp)
+                    | Some(jump @ Action::PageDown)
+                    | Some(jump @ Action::Home)
+                    | Some(jump @ Action::End) => {
+                        search = false;
+                        let page = std::cmp::max(visiable_nums, 1);
+                        cur = match jump {
+                            Action::PageUp => cur - page,
+                            Action::PageDown => cur + page,
+                            Action::Home => 0,
+                            _ => len - 1,
+                        };
+                        cur = std::cmp::max(0, std::cmp::min(cur, len - 1));
+                        start = std::cmp::max(
+                            0,
+                            std::cmp::min(cur - (visiable_nums - 1) / 2, len - visiable_nums),
+                        );
+                        break;
+                    }
+                    Some(Action::Confirm) => {
+                        assert!(!dirty);
+                        if list_idx.is_empty() {
+                            continue;
                         }
-                        event::KeyCode::Enter => {
-                            assert!(!dirty);
+                        let chosen: Vec<T> = if selected.contains(&true) {
+                            (0..list.len())
+                                .filter(|i| selected[*i])
+                                .map(|i| list[i].clone())
+                                .collect()
+                        } else {
+                            vec![list[list_idx[cur as usize]].clone()]
+                        };
+                        let echo = match chosen.len() {
+                            1 => chosen[0].line(icons),
+                            n => format!("{} selected", n),
+                        };
+                        execute!(
+                            self.writer,
+                            cursor::MoveTo(begin_x, begin_y),
+                            terminal::Clear(terminal::ClearType::CurrentLine),
+                            style::Print(echo),
+                            cursor::MoveToNextLine(1),
+                            terminal::Clear(terminal::ClearType::CurrentLine),
+                        )?;
+                        return Ok(Some(chosen));
+                    }
+                    Some(Action::Toggle) => {
+                        if !list_idx.is_empty() {
+                            let idx = list_idx[cur as usize];
+                            selected[idx] = !selected[idx];
+                        }
+                        break;
+                    }
+                    Some(Action::Interrupt) => {
+                        drop(reset);
+                        return Err(Error::StringError("Terminate by Ctrl-C".to_owned()));
+                    }
+                    Some(Action::Cancel) => {
+                        if search {
+                            search = false;
+                            search_buf.clear();
+                            dirty = true;
+                        } else if cancelable {
                             execute!(
                                 self.writer,
                                 cursor::MoveTo(begin_x, begin_y),
-                                terminal::Clear(terminal::ClearType::CurrentLine),
-                                style::Print(format!("{}", &list[list_idx[cur as usize]])),
-                                cursor::MoveToNextLine(1),
-                                terminal::Clear(terminal::ClearType::CurrentLine),
+                                terminal::Clear(terminal::ClearType::FromCursorDown),
                             )?;
-                            return Ok(list[list_idx[cur as usize]].clone());
+                            return Ok(None);
                         }
-                        event::KeyCode::Char('c')
-                            if event.modifiers.contains(event::KeyModifiers::CONTROL) =>
-                        {
-                            drop(reset);
-                            return Err(Error::StringError("Terminate by Ctrl-C".to_owned()));
+                        break;
+                    }
+                    Some(Action::Search) => {
+                        search = true;
+                        break;
+                    }
+                    Some(Action::Copy) => {
+                        let copyable = list_idx
+                            .get(cur as usize)
+                            .is_some_and(|&idx| list[idx].clip(Clip::Summary).is_some());
+                        if copyable {
+                            search = false;
+                            copying = true;
                         }
-                        event::KeyCode::Esc => {
-                            if search {
+                        break;
+                    }
+                    Some(flag @ Action::Pin) | Some(flag @ Action::Archive) => {
+                        let idx = match list_idx.get(cur as usize) {
+                            Some(&idx) if list[idx].flaggable() => idx,
+                            _ => break,
+                        };
+                        state.flag = Some(match flag {
+                            Action::Pin => Flag::Pinned,
+                            _ => Flag::Archived,
+                        });
+                        execute!(
+                            self.writer,
+                            cursor::MoveTo(begin_x, begin_y),
+                            terminal::Clear(terminal::ClearType::FromCursorDown),
+                        )?;
+                        return Ok(Some(vec![list[idx].clone()]));
+                    }
+                    Some(Action::Backspace) => {
+                        if search {
+                            start = 0;
+                            cur = 0;
+                            search_buf.pop();
+                            dirty = true;
+                            if search_buf.is_empty() {
                                 search = false;
-                                search_buf.clear();
-                                dirty = true;
                             }
-                            break;
                         }
-                        event::KeyCode::Backspace => {
-                            if search {
-                                start = 0;
-                                cur = 0;
-                                search_buf.pop();
-                                dirty = true;
-                                if search_buf.is_empty() {
-                                    search = false;
-                                }
-                            }
-                            break;
-                        }
-                        event::KeyCode::Char(c) => {
+                        break;
+                    }
+                    None => match key.map(|key| (key.code, key.modifiers)) {
+                        Some((event::KeyCode::Char(c), modifiers))
+                            if (search || self.keymap.type_to_search())
+                                && !modifiers.intersects(
+                                    event::KeyModifiers::CONTROL | event::KeyModifiers::ALT,
+                                ) =>
+                        {
                             search = true;
                             start = 0;
                             cur = 0;
@@ -222,7 +594,6 @@ where
                         }
                         _ => continue,
                     },
-                    _ => continue,
                 }
             }
         }
@@ -230,72 +601,463 @@ where
 
     pub fn read_input_with(
         &mut self,
+        field: &str,
         editor: bool,
         hint: Option<String>,
     ) -> Result<Option<String>> {
-        let (begin_x, mut begin_y) = cursor::position()?;
-        let (w, _) = terminal::size()?;
-        let ydist = |n: u16, w: u16, begin: u16| {
-            if n > (w - begin) {
-                1u16 + (n - w + begin) / w
-            } else {
-                0u16
+        self.read_line(field, editor, hint, &[])
+    }
+
+    pub fn read_input(&mut self, field: &str, editor: bool) -> Result<Option<String>> {
+        self.read_line(field, editor, None, &[])
+    }
+
+    /// Reads a line with `hint` prefilled for editing, `field` keys its
+    /// history and Tab completes from `candidates`. A multi-line hint can't
+    /// be edited inline, it's kept when nothing is typed. `!cmd` opens the
+    /// hint in editor `cmd` when `editor` is set, unless it is the hint left
+    /// as is. Plain input keeps the hint on an empty line.
+    pub fn read_line(
+        &mut self,
+        field: &str,
+        editor: bool,
+        hint: Option<String>,
+        candidates: &[String],
+    ) -> Result<Option<String>> {
+        self.read(field, editor, hint, candidates, false)
+    }
+
+    /// Like `read_line`, but Alt-Enter breaks the line, after which Enter
+    /// continues Markdown lists and quotes and Ctrl-D finishes. Plain input
+    /// reads lines up to a lone `.`, unless the first one is empty.
+    pub fn read_text(
+        &mut self,
+        field: &str,
+        editor: bool,
+        hint: Option<String>,
+    ) -> Result<Option<String>> {
+        self.read(field, editor, hint, &[], true)
+    }
+
+    fn read(
+        &mut self,
+        field: &str,
+        editor: bool,
+        hint: Option<String>,
+        candidates: &[String],
+        multiline: bool,
+    ) -> Result<Option<String>> {
+        let placeholder = !multiline && hint.as_ref().is_some_and(|h| h.contains('\n'));
+        let line = if self.plain {
+            if let Some(hint) = hint.as_deref().filter(|h| !h.is_empty()) {
+                let first = hint.lines().next().unwrap_or("");
+                let more = if first.len() < hint.len() { "..." } else { "" };
+                let keep = format!("[Enter keeps `{}{}`]", first, more);
+                writeln!(self.writer, "{}", self.theme.help.apply(keep))?;
+            }
+            if multiline {
+                let end = "[end with a line holding only `.`]";
+                writeln!(self.writer, "{}", self.theme.help.apply(end))?;
             }
+            write!(self.writer, "{}", marks(self.theme.icons).0)?;
+            self.writer.flush()?;
+            let mut text = self.read_plain()?;
+            while multiline && !text.trim().is_empty() {
+                match self.read_plain_line()? {
+                    Some(line) if line.trim() != "." => text = text + "\n" + &line,
+                    _ => break,
+                }
+            }
+            text.trim().to_owned()
+        } else if placeholder {
+            self.edit_line(field, "", hint.as_deref(), candidates, multiline)?
+        } else {
+            let prefill = hint.as_deref().unwrap_or("");
+            self.edit_line(field, prefill, None, candidates, multiline)?
         };
-        match hint {
-            Some(ref hint) => {
-                if let Some(n) = hint.find('\n') {
-                    begin_y = begin_y - ydist((n + 3) as u16, w, begin_x);
-                    write!(self.writer, "{}...", hint[..n].dark_grey())?
-                } else {
-                    begin_y = begin_y - ydist(hint.len() as u16, w, begin_x);
-                    write!(self.writer, "{}", hint[..].dark_grey())?
+        let line = line.trim();
+        self.history.push(field, line);
+        self.prompted = true;
+        let unchanged = hint.as_deref().is_some_and(|h| h.trim() == line);
+        Ok(if line.is_empty() {
+            hint.filter(|_| placeholder || self.plain)
+        } else if editor && line.starts_with('!') && !unchanged {
+            read_from_editor(line[1..].trim(), hint)?
+        } else {
+            Some(line.to_owned())
+        })
+    }
+
+    fn edit_line(
+        &mut self,
+        field: &str,
+        prefill: &str,
+        placeholder: Option<&str>,
+        candidates: &[String],
+        multiline: bool,
+    ) -> Result<String> {
+        let (begin_x, mut begin_y) = self.events.position()?;
+        let mut line = LineBuffer::new(prefill);
+        let history = self.history.entries(field).to_vec();
+        let mut recall = history.len();
+        let mut draft = String::new();
+        let mut tips = String::new();
+
+        let tty = self.events.is_terminal();
+        if tty {
+            terminal::enable_raw_mode()?;
+        }
+        let _reset = defer(|| {
+            if tty {
+                terminal::disable_raw_mode().unwrap();
+            }
+        });
+        loop {
+            let text = line.text();
+            let broken = text.contains('\n');
+            let help = match (multiline, broken) {
+                (true, false) => "(*Newline [Alt-Enter], *Confirm [Enter])",
+                (true, true) => "(*Newline [Enter], *Finish [Ctrl-D])",
+                _ => "",
+            };
+            let shown_tips = if tips.is_empty() { help } else { &tips[..] };
+            begin_y = self.draw_line(&line, begin_x, begin_y, placeholder, shown_tips)?;
+
+            let key = match self.events.read()? {
+                event::Event::Key(key) => key,
+                _ => continue,
+            };
+            let ctrl = key.modifiers.contains(event::KeyModifiers::CONTROL);
+            let alt = key.modifiers.contains(event::KeyModifiers::ALT);
+            tips.clear();
+            let mut step = 0;
+            match key.code {
+                event::KeyCode::Enter if multiline && (alt || broken) => line.newline(),
+                event::KeyCode::Enter => break,
+                event::KeyCode::Esc => line.set(prefill),
+                event::KeyCode::Left if ctrl => line.word_left(),
+                event::KeyCode::Right if ctrl => line.word_right(),
+                event::KeyCode::Left => line.left(),
+                event::KeyCode::Right => line.right(),
+                event::KeyCode::Home => line.home(),
+                event::KeyCode::End => line.end(),
+                event::KeyCode::Up if !line.up() => step = -1,
+                event::KeyCode::Down if !line.down() => step = 1,
+                event::KeyCode::Backspace if alt => line.delete_word_left(),
+                event::KeyCode::Backspace => line.backspace(),
+                event::KeyCode::Delete => line.delete(),
+                event::KeyCode::Tab => {
+                    let matches = line.complete(candidates);
+                    if matches.len() > 1 {
+                        tips = matches.join("  ");
+                    }
                 }
+                event::KeyCode::Char(c) if ctrl => match c {
+                    'c' => return Err(Error::StringError("Terminate by Ctrl-C".to_owned())),
+                    'd' if multiline => break,
+                    'j' if multiline => line.newline(),
+                    'a' => line.home(),
+                    'e' => line.end(),
+                    'b' => line.left(),
+                    'f' => line.right(),
+                    'h' => line.backspace(),
+                    'd' => line.delete(),
+                    'w' => line.delete_word_left(),
+                    'u' => line.kill_to_start(),
+                    'k' => line.kill_to_end(),
+                    'p' if !line.up() => step = -1,
+                    'n' if !line.down() => step = 1,
+                    _ => {}
+                },
+                event::KeyCode::Char(c) if alt => match c {
+                    'b' => line.word_left(),
+                    'f' => line.word_right(),
+                    'd' => line.delete_word_right(),
+                    _ => {}
+                },
+                event::KeyCode::Char(c) => line.insert(c),
+                _ => {}
+            }
+            if step < 0 && recall > 0 {
+                if recall == history.len() {
+                    draft = line.text();
+                }
+                recall -= 1;
+                line.set(&history[recall]);
+            } else if step > 0 && recall < history.len() {
+                recall += 1;
+                line.set(history.get(recall).unwrap_or(&draft));
             }
-            None => {}
         }
-        execute!(self.writer, cursor::MoveTo(begin_x, begin_y))?;
+        let text = line.text();
+        begin_y = self.draw_line(&line, begin_x, begin_y, None, "")?;
+        let rows = line_rows(&text, begin_x, self.events.size()?.0) as u16;
+        drop(_reset);
+        execute!(self.writer, cursor::MoveTo(0, begin_y + rows - 1))?;
+        writeln!(self.writer)?;
+        self.writer.flush()?;
+        Ok(text)
+    }
 
-        let mut buf = String::new();
-        self.reader.read_line(&mut buf)?;
-        let postbuf = buf.trim();
-        Ok(if postbuf == "" || postbuf == "\n" {
-            hint
-        } else if editor && postbuf.starts_with("!") {
-            read_from_editor(postbuf[1..].trim(), hint)?
+    /// Draws `line` from (`begin_x`, `begin_y`) with `tips` below it, each
+    /// line of the text starting at `begin_x`. Scrolls the screen when it
+    /// doesn't fit, returns where the text begins then.
+    fn draw_line(
+        &mut self,
+        line: &LineBuffer,
+        begin_x: u16,
+        mut begin_y: u16,
+        placeholder: Option<&str>,
+        tips: &str,
+    ) -> Result<u16> {
+        let (w, h) = self.events.size()?;
+        let help = self.theme.help;
+        let text = line.text();
+        let shown = match placeholder {
+            Some(placeholder) if text.is_empty() => {
+                format!("{}...", placeholder.lines().next().unwrap_or(""))
+            }
+            _ => text.clone(),
+        };
+        let rows = line_rows(&shown, begin_x, w) + if tips.is_empty() { 0 } else { 1 };
+        let overflow = (begin_y as usize + rows).saturating_sub(h as usize) as u16;
+        if overflow > 0 {
+            queue!(self.writer, terminal::ScrollUp(overflow))?;
+            begin_y = begin_y.saturating_sub(overflow);
+        }
+        queue!(
+            self.writer,
+            cursor::MoveTo(begin_x, begin_y),
+            terminal::Clear(terminal::ClearType::FromCursorDown),
+        )?;
+        let mut row = begin_y;
+        for text_line in shown.split('\n') {
+            queue!(self.writer, cursor::MoveTo(begin_x, row))?;
+            if text.is_empty() {
+                queue!(
+                    self.writer,
+                    style::PrintStyledContent(help.apply(text_line.to_owned()))
+                )?;
+            } else {
+                queue!(self.writer, style::Print(text_line))?;
+            }
+            row += line_rows(text_line, begin_x, w) as u16;
+        }
+        if !tips.is_empty() {
+            queue!(
+                self.writer,
+                cursor::MoveTo(0, row),
+                style::PrintStyledContent(help.apply(tips.to_owned())),
+            )?;
+        }
+        let head = line.head();
+        let (above, current) = head.rsplit_once('\n').unwrap_or(("", &head[..]));
+        let above = if head.contains('\n') {
+            above.split('\n').map(|l| line_rows(l, begin_x, w)).sum()
         } else {
-            Some(postbuf.to_string())
-        })
+            0
+        };
+        let pos = begin_x as usize + width(current);
+        let w = std::cmp::max(w, 1) as usize;
+        queue!(
+            self.writer,
+            cursor::MoveTo((pos % w) as u16, begin_y + (above + pos / w) as u16)
+        )?;
+        self.writer.flush()?;
+        Ok(begin_y)
+    }
+
+    /// Lists `list` numbered and reads the numbers of the chosen items,
+    /// several separated by spaces or `,` when `multi`. Any other answer
+    /// narrows the list to the items matching it, and an empty one brings
+    /// the whole list back, or leaves when `cancelable`.
+    fn pick_plain<T>(
+        &mut self,
+        list: Vec<T>,
+        multi: bool,
+        cancelable: bool,
+    ) -> Result<Option<Vec<T>>>
+    where
+        T: std::fmt::Display + Clone + Searchable,
+    {
+        let mut shown: Vec<_> = (0..list.len()).collect();
+        let mut relist = true;
+        loop {
+            if relist {
+                for (n, &i) in shown.iter().enumerate() {
+                    writeln!(
+                        self.writer,
+                        "{:>4}. {}",
+                        n + 1,
+                        list[i].line(self.theme.icons)
+                    )?;
+                }
+            }
+            let ask = if multi { "numbers" } else { "number" };
+            write!(
+                self.writer,
+                "{}{} or filter text: ",
+                marks(self.theme.icons).0,
+                ask
+            )?;
+            self.writer.flush()?;
+            let line = self.read_plain()?;
+            let line = line.trim();
+            let words: Vec<_> = line
+                .split(|c: char| c.is_whitespace() || c == ',')
+                .filter(|w| !w.is_empty())
+                .collect();
+            let numbers: Option<Vec<usize>> = words.iter().map(|w| w.parse().ok()).collect();
+            relist = true;
+            match numbers {
+                _ if line.is_empty() && cancelable => return Ok(None),
+                _ if line.is_empty() => shown = (0..list.len()).collect(),
+                Some(numbers) if multi || numbers.len() == 1 => {
+                    if numbers.iter().all(|&n| n >= 1 && n <= shown.len()) {
+                        let chosen = numbers.iter().map(|&n| list[shown[n - 1]].clone());
+                        return Ok(Some(chosen.collect()));
+                    }
+                    writeln!(self.writer, "enter numbers between 1 and {}", shown.len())?;
+                    relist = false;
+                }
+                Some(_) => {
+                    writeln!(self.writer, "enter a single number")?;
+                    relist = false;
+                }
+                None => {
+                    let matches: Vec<_> = (0..list.len())
+                        .filter(|&i| list[i].is_match(line))
+                        .collect();
+                    if matches.is_empty() {
+                        writeln!(self.writer, "nothing matches `{}`", line)?;
+                        relist = false;
+                    } else {
+                        shown = matches;
+                    }
+                }
+            }
+        }
+    }
+
+    /// A line of the reader, failing at its end rather than looping on it.
+    fn read_plain(&mut self) -> Result<String> {
+        self.read_plain_line()?
+            .ok_or_else(|| Error::StringError("unexpected end of input".to_owned()))
     }
 
-    pub fn read_input(&mut self, editor: bool) -> Result<Option<String>> {
+    /// A line of plain input, `None` at the end of it.
+    fn read_plain_line(&mut self) -> Result<Option<String>> {
         let mut buf = String::new();
-        self.reader.read_line(&mut buf)?;
-        let postbuf = buf.trim();
-        Ok(if postbuf == "" || postbuf == "\n" {
-            None
-        } else if editor && postbuf.starts_with("!") {
-            read_from_editor(postbuf[1..].trim(), None)?
-        } else {
-            Some(postbuf.to_string())
-        })
+        if self.reader.read_line(&mut buf)? == 0 {
+            return Ok(None);
+        }
+        if !self.events.echoes() {
+            // the answer isn't echoed, keep the next question on its own line
+            writeln!(self.writer)?;
+        }
+        Ok(Some(buf.trim_end_matches(&['\r', '\n'][..]).to_owned()))
+    }
+
+    /// The history with the answers read, `None` when nothing was read.
+    pub fn take_history(&mut self) -> Option<History> {
+        Some(std::mem::take(&mut self.history)).filter(|_| self.prompted)
+    }
+}
+
+/// An item along with its position in the picked list.
+#[derive(Clone)]
+struct Indexed<T>(usize, T);
+
+impl<T: std::fmt::Display> std::fmt::Display for Indexed<T> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        self.1.fmt(f)
     }
 }
 
+impl<T: std::fmt::Display + Searchable> Searchable for Indexed<T> {
+    fn is_match(&self, pat: &str) -> bool {
+        self.1.is_match(pat)
+    }
+    fn line(&self, icons: Icons) -> String {
+        self.1.line(icons)
+    }
+}
+
+/// The (current, toggled) item marks of pickers.
+fn marks(icons: Icons) -> (&'static str, &'static str) {
+    (icons.choose("❯ ", "> "), icons.choose("✓ ", "x "))
+}
+
+/// Rows taken by each line of `text` printed from column `begin_x`.
+fn line_rows(text: &str, begin_x: u16, width: u16) -> usize {
+    let width = std::cmp::max(width, 1) as usize;
+    text.split('\n')
+        .map(|l| (begin_x as usize + self::width(l)) / width + 1)
+        .sum()
+}
+
 impl Searchable for char {
-    fn is_match(&self, _: &String) -> bool {
+    fn is_match(&self, _: &str) -> bool {
         false
     }
+    fn line(&self, icons: Icons) -> String {
+        icons.icon(*self)
+    }
 }
 
 impl Searchable for String {
-    fn is_match(&self, pat: &String) -> bool {
+    fn is_match(&self, pat: &str) -> bool {
         self.contains(pat)
     }
 }
 
 impl Searchable for &str {
-    fn is_match(&self, pat: &String) -> bool {
+    fn is_match(&self, pat: &str) -> bool {
         self.contains(pat)
     }
 }
+
+#[cfg(test)]
+mod test {
+    use super::super::ievent::Script;
+    use super::*;
+    #[test]
+    fn test_basis() {
+        let input = "\n9\nt\nx\n2\n\n3, 1\nnew summary\n\n";
+        let mut core = Core::new(input.as_bytes(), vec![]);
+        core.plain(true);
+        let list = vec!["one", "two", "three"];
+        let pick = core.read_input_from(list.clone(), Direction::Vertical);
+        assert_eq!(pick.unwrap(), "three");
+        let mut state = PickState::default();
+        let pick = core.read_input_resume(list.clone(), Direction::Vertical, &mut state);
+        assert!(pick.unwrap().is_none());
+        let picks = core.read_inputs_from(list, Direction::Vertical);
+        assert_eq!(picks.unwrap(), ["three", "one"]);
+        let line = core.read_input_with("summary", false, Some("old".to_owned()));
+        assert_eq!(line.unwrap().as_deref(), Some("new summary"));
+        let line = core.read_input_with("summary", false, Some("old".to_owned()));
+        assert_eq!(line.unwrap().as_deref(), Some("old"));
+        assert!(core.read_input("summary", false).is_err());
+        assert_eq!(
+            core.take_history().unwrap().entries("summary"),
+            ["new summary"]
+        );
+
+        let input = "- one\n- two\n.\n\nlast\n";
+        let mut core = Core::new(input.as_bytes(), vec![]);
+        core.plain(true);
+        let text = core.read_text("detail", false, None);
+        assert_eq!(text.unwrap().as_deref(), Some("- one\n- two"));
+        let text = core.read_text("detail", false, Some("old".to_owned()));
+        assert_eq!(text.unwrap().as_deref(), Some("old"));
+        let text = core.read_text("detail", false, None);
+        assert_eq!(text.unwrap().as_deref(), Some("last"));
+
+        let mut core = Core::new(std::io::empty(), vec![]);
+        core.events(Box::new(Script::new(Script::keys("\n"))));
+        let shot = Some("![shot](a.png)".to_owned());
+        let line = core.read_input_with("detail", true, shot.clone());
+        assert_eq!(line.unwrap(), shot);
+    }
+}
@@ -20,7 +20,11 @@ pub fn read_from_editor(editor: impl AsRef<str>, hint: Option<String>) -> Result
 
     temp_dir.close()?;
 
-    Ok(if content != "" { Some(content) } else { None })
+    Ok(if !content.is_empty() {
+        Some(content)
+    } else {
+        None
+    })
 }
 
 #[cfg(test)]
@@ -28,6 +32,6 @@ mod test {
     use super::*;
     #[test]
     fn test_basis() {
-        assert_eq!(read_from_editor("touch".to_owned(), None).unwrap(), None);
+        assert_eq!(read_from_editor("touch", None).unwrap(), None);
     }
 }
@@ -0,0 +1,149 @@
+use super::ikeys::parse_key;
+use crate::{Error, Result};
+use crossterm::{cursor, event, event::Event, terminal};
+
+/// Where pickers and prompts get their events and screen geometry from.
+pub trait EventSource {
+    /// Blocks until the next event.
+    fn read(&mut self) -> Result<Event>;
+    fn size(&self) -> Result<(u16, u16)>;
+    fn position(&mut self) -> Result<(u16, u16)>;
+    /// Whether this is the real terminal, whose raw mode and mouse capture
+    /// have to be switched around reading.
+    fn is_terminal(&self) -> bool;
+    /// Whether lines typed in plain mode show up on the screen by themselves.
+    fn echoes(&self) -> bool;
+}
+
+/// The terminal ideas runs in.
+pub struct Terminal;
+
+impl EventSource for Terminal {
+    fn read(&mut self) -> Result<Event> {
+        Ok(event::read()?)
+    }
+
+    fn size(&self) -> Result<(u16, u16)> {
+        Ok(terminal::size()?)
+    }
+
+    fn position(&mut self) -> Result<(u16, u16)> {
+        Ok(cursor::position()?)
+    }
+
+    fn is_terminal(&self) -> bool {
+        true
+    }
+
+    fn echoes(&self) -> bool {
+        atty::is(atty::Stream::Stdin)
+    }
+}
+
+/// Whether pickers can't be drawn: input or output isn't a terminal, or
+/// the terminal can't move the cursor, as in editor shell buffers.
+pub fn plain_terminal() -> bool {
+    let dumb = std::env::var("TERM").map_or(true, |term| term.is_empty() || term == "dumb");
+    dumb || !atty::is(atty::Stream::Stdin) || !atty::is(atty::Stream::Stdout)
+}
+
+/// Replays prepared events on an 80x24 screen, for driving the interaction
+/// layer without a terminal.
+pub struct Script {
+    events: std::collections::VecDeque<Event>,
+}
+
+impl Script {
+    pub fn new(events: Vec<Event>) -> Script {
+        Script {
+            events: events.into(),
+        }
+    }
+
+    /// Reads `ideas --keys`: typed text with keys named in angle brackets as
+    /// in `ideas config keys`, like `buy milk<enter><alt-p>`, where `<<`
+    /// types `<`.
+    pub fn parse(script: &str) -> Result<Script> {
+        let mut events = vec![];
+        let mut rest = script;
+        while let Some(c) = rest.chars().next() {
+            rest = &rest[c.len_utf8()..];
+            let key = match c {
+                '<' if rest.starts_with('<') => {
+                    rest = &rest[1..];
+                    event::KeyCode::Char('<').into()
+                }
+                '<' => {
+                    let end = rest.find('>').ok_or_else(|| {
+                        Error::StringError(format!("unclosed `<` in keys `{}`", script))
+                    })?;
+                    let key = parse_key(&rest[..end])?;
+                    rest = &rest[end + 1..];
+                    key
+                }
+                '\n' => event::KeyCode::Enter.into(),
+                c => event::KeyCode::Char(c).into(),
+            };
+            events.push(Event::Key(key));
+        }
+        Ok(Script::new(events))
+    }
+
+    /// Key presses typing `text`, where `\n` is Enter.
+    #[cfg(test)]
+    pub fn keys(text: &str) -> Vec<Event> {
+        text.chars()
+            .map(|c| match c {
+                '\n' => event::KeyCode::Enter,
+                c => event::KeyCode::Char(c),
+            })
+            .map(|code| Event::Key(code.into()))
+            .collect()
+    }
+}
+
+impl EventSource for Script {
+    fn read(&mut self) -> Result<Event> {
+        self.events
+            .pop_front()
+            .ok_or_else(|| Error::StringError("the script ran out of events".to_owned()))
+    }
+
+    fn size(&self) -> Result<(u16, u16)> {
+        Ok((80, 24))
+    }
+
+    fn position(&mut self) -> Result<(u16, u16)> {
+        Ok((2, 0))
+    }
+
+    fn is_terminal(&self) -> bool {
+        false
+    }
+
+    fn echoes(&self) -> bool {
+        false
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use event::{KeyCode, KeyEvent, KeyModifiers};
+    #[test]
+    fn test_basis() {
+        let mut script = Script::parse("a<<<enter><alt-p>\n").unwrap();
+        let mut next = || match script.read().unwrap() {
+            Event::Key(key) => key,
+            _ => unreachable!(),
+        };
+        assert_eq!(next().code, KeyCode::Char('a'));
+        assert_eq!(next().code, KeyCode::Char('<'));
+        assert_eq!(next().code, KeyCode::Enter);
+        assert_eq!(next(), KeyEvent::new(KeyCode::Char('p'), KeyModifiers::ALT));
+        assert_eq!(next().code, KeyCode::Enter);
+        assert!(script.read().is_err());
+        assert!(Script::parse("<enter").is_err());
+        assert!(Script::parse("<hyper-x>").is_err());
+    }
+}
@@ -0,0 +1,256 @@
+use crate::error::{Error, Result};
+use crossterm::event::{KeyCode, KeyEvent, KeyModifiers};
+use std::collections::BTreeMap;
+
+pub const PRESETS: &[&str; 3] = &["default", "vi", "emacs"];
+
+/// What a key does in a picker.
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum Action {
+    Up,
+    Down,
+    PageUp,
+    PageDown,
+    Home,
+    End,
+    Confirm,
+    Cancel,
+    Interrupt,
+    Search,
+    Backspace,
+    Toggle,
+    Copy,
+    Pin,
+    Archive,
+}
+
+impl Action {
+    fn from_name(name: &str) -> Result<Action> {
+        Ok(match name {
+            "up" => Action::Up,
+            "down" => Action::Down,
+            "page-up" => Action::PageUp,
+            "page-down" => Action::PageDown,
+            "home" => Action::Home,
+            "end" => Action::End,
+            "confirm" => Action::Confirm,
+            "cancel" => Action::Cancel,
+            "interrupt" => Action::Interrupt,
+            "search" => Action::Search,
+            "backspace" => Action::Backspace,
+            "toggle" => Action::Toggle,
+            "copy" => Action::Copy,
+            "pin" => Action::Pin,
+            "archive" => Action::Archive,
+            _ => return Err(Error::StringError(format!("unknown key action `{}`", name))),
+        })
+    }
+}
+
+pub struct KeyMap {
+    binds: Vec<(KeyEvent, Action)>,
+    /// Whether an unbound printable key starts a search, as opposed to
+    /// requiring the `search` action first.
+    type_to_search: bool,
+}
+
+impl KeyMap {
+    pub fn preset(name: &str) -> Result<KeyMap> {
+        let mut keymap = KeyMap {
+            binds: vec![],
+            type_to_search: true,
+        };
+        keymap.bind_all(&[
+            ("up", "up"),
+            ("left", "up"),
+            ("backtab", "up"),
+            ("down", "down"),
+            ("right", "down"),
+            ("tab", "down"),
+            ("pageup", "page-up"),
+            ("pagedown", "page-down"),
+            ("home", "home"),
+            ("end", "end"),
+            ("enter", "confirm"),
+            ("esc", "cancel"),
+            ("ctrl-c", "interrupt"),
+            ("backspace", "backspace"),
+            ("space", "toggle"),
+            ("ctrl-y", "copy"),
+            ("alt-p", "pin"),
+            ("alt-a", "archive"),
+        ])?;
+        match name {
+            "default" => {}
+            "vi" => {
+                keymap.type_to_search = false;
+                keymap.bind_all(&[
+                    ("k", "up"),
+                    ("h", "up"),
+                    ("j", "down"),
+                    ("l", "down"),
+                    ("ctrl-b", "page-up"),
+                    ("ctrl-u", "page-up"),
+                    ("ctrl-f", "page-down"),
+                    ("ctrl-d", "page-down"),
+                    ("g", "home"),
+                    ("G", "end"),
+                    ("q", "cancel"),
+                    ("/", "search"),
+                    ("y", "copy"),
+                    ("p", "pin"),
+                    ("A", "archive"),
+                ])?;
+            }
+            "emacs" => keymap.bind_all(&[
+                ("ctrl-p", "up"),
+                ("ctrl-b", "up"),
+                ("ctrl-n", "down"),
+                ("ctrl-f", "down"),
+                ("alt-v", "page-up"),
+                ("ctrl-v", "page-down"),
+                ("alt-<", "home"),
+                ("alt->", "end"),
+                ("ctrl-g", "cancel"),
+                ("ctrl-s", "search"),
+                ("ctrl-h", "backspace"),
+                ("alt-w", "copy"),
+            ])?,
+            _ => {
+                return Err(Error::StringError(format!(
+                    "unknown keymap `{}`, expect one of {}",
+                    name,
+                    PRESETS.join(", ")
+                )))
+            }
+        }
+        Ok(keymap)
+    }
+
+    /// A preset with user bindings (`"ctrl-j" = "down"`) layered on top.
+    pub fn from_config(preset: &str, binds: &BTreeMap<String, String>) -> Result<KeyMap> {
+        let mut keymap = KeyMap::preset(preset)?;
+        for (key, action) in binds {
+            keymap.bind(key, action)?;
+        }
+        Ok(keymap)
+    }
+
+    pub fn bind(&mut self, key: &str, action: &str) -> Result<()> {
+        let key = parse_key(key)?;
+        let action = Action::from_name(action)?;
+        self.binds.retain(|(k, _)| k != &key);
+        self.binds.push((key, action));
+        Ok(())
+    }
+
+    fn bind_all(&mut self, binds: &[(&str, &str)]) -> Result<()> {
+        for (key, action) in binds {
+            self.bind(key, action)?;
+        }
+        Ok(())
+    }
+
+    /// The action of `key`, `None` means it is text: typed into the search
+    /// while `searching`, or starting one if the keymap allows it.
+    pub fn action(&self, key: &KeyEvent, searching: bool) -> Option<Action> {
+        let key = normalize(*key);
+        let plain = !key
+            .modifiers
+            .intersects(KeyModifiers::CONTROL | KeyModifiers::ALT);
+        if let (KeyCode::Char(_), true, true) = (key.code, plain, searching) {
+            return None;
+        }
+        self.binds
+            .iter()
+            .find(|(k, _)| k == &key)
+            .map(|(_, action)| *action)
+    }
+
+    pub fn type_to_search(&self) -> bool {
+        self.type_to_search
+    }
+}
+
+impl Default for KeyMap {
+    fn default() -> KeyMap {
+        KeyMap::preset(PRESETS[0]).unwrap()
+    }
+}
+
+/// Shift is already part of an upper case char.
+fn normalize(mut key: KeyEvent) -> KeyEvent {
+    if let KeyCode::Char(_) = key.code {
+        key.modifiers.remove(KeyModifiers::SHIFT);
+    }
+    key
+}
+
+/// Reads a key name such as `ctrl-u`, `alt-p`, `enter` or `G`.
+pub fn parse_key(s: &str) -> Result<KeyEvent> {
+    let err = || Error::StringError(format!("invalid key `{}`", s));
+    let mut modifiers = KeyModifiers::empty();
+    let mut rest = s;
+    loop {
+        let lower = rest.to_lowercase();
+        if rest.len() > 5 && lower.starts_with("ctrl-") {
+            modifiers |= KeyModifiers::CONTROL;
+            rest = &rest[5..];
+        } else if rest.len() > 4 && lower.starts_with("alt-") {
+            modifiers |= KeyModifiers::ALT;
+            rest = &rest[4..];
+        } else {
+            break;
+        }
+    }
+    let mut chars = rest.chars();
+    let code = match (chars.next(), chars.next()) {
+        (Some(c), None) => KeyCode::Char(c),
+        _ => match rest.to_lowercase().as_str() {
+            "up" => KeyCode::Up,
+            "down" => KeyCode::Down,
+            "left" => KeyCode::Left,
+            "right" => KeyCode::Right,
+            "pageup" => KeyCode::PageUp,
+            "pagedown" => KeyCode::PageDown,
+            "home" => KeyCode::Home,
+            "end" => KeyCode::End,
+            "enter" => KeyCode::Enter,
+            "esc" => KeyCode::Esc,
+            "backspace" => KeyCode::Backspace,
+            "delete" => KeyCode::Delete,
+            "tab" => KeyCode::Tab,
+            "backtab" => KeyCode::BackTab,
+            "space" => KeyCode::Char(' '),
+            _ => return Err(err()),
+        },
+    };
+    Ok(normalize(KeyEvent { code, modifiers }))
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    #[test]
+    fn test_basis() {
+        let key = |code, modifiers| KeyEvent { code, modifiers };
+        let vi = KeyMap::preset("vi").unwrap();
+        let j = key(KeyCode::Char('j'), KeyModifiers::empty());
+        assert_eq!(vi.action(&j, false), Some(Action::Down));
+        assert_eq!(vi.action(&j, true), None);
+        let end = key(KeyCode::Char('G'), KeyModifiers::SHIFT);
+        assert_eq!(vi.action(&end, false), Some(Action::End));
+        let emacs = KeyMap::preset("emacs").unwrap();
+        let next = key(KeyCode::Char('n'), KeyModifiers::CONTROL);
+        assert_eq!(emacs.action(&next, true), Some(Action::Down));
+        let save = key(KeyCode::Char('w'), KeyModifiers::ALT);
+        assert_eq!(emacs.action(&save, false), Some(Action::Copy));
+        let mut binds = BTreeMap::new();
+        binds.insert("ctrl-j".to_owned(), "down".to_owned());
+        let custom = KeyMap::from_config("default", &binds).unwrap();
+        let ctrl_j = key(KeyCode::Char('j'), KeyModifiers::CONTROL);
+        assert_eq!(custom.action(&ctrl_j, false), Some(Action::Down));
+        assert!(KeyMap::preset("nano").is_err());
+        assert!(parse_key("ctrl-").is_err());
+    }
+}
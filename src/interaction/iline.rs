@@ -0,0 +1,308 @@
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+
+const HISTORY_LIMIT: usize = 100;
+
+/// Text of a prompt being edited, with the cursor as a char index.
+pub struct LineBuffer {
+    chars: Vec<char>,
+    pos: usize,
+}
+
+impl LineBuffer {
+    pub fn new(text: &str) -> LineBuffer {
+        let chars: Vec<char> = text.chars().collect();
+        let pos = chars.len();
+        LineBuffer { chars, pos }
+    }
+
+    pub fn text(&self) -> String {
+        self.chars.iter().collect()
+    }
+
+    /// Text before the cursor.
+    pub fn head(&self) -> String {
+        self.chars[..self.pos].iter().collect()
+    }
+
+    pub fn set(&mut self, text: &str) {
+        *self = LineBuffer::new(text);
+    }
+
+    pub fn insert(&mut self, c: char) {
+        self.chars.insert(self.pos, c);
+        self.pos += 1;
+    }
+
+    pub fn insert_str(&mut self, s: &str) {
+        s.chars().for_each(|c| self.insert(c));
+    }
+
+    pub fn left(&mut self) {
+        self.pos = self.pos.saturating_sub(1);
+    }
+
+    pub fn right(&mut self) {
+        self.pos = std::cmp::min(self.pos + 1, self.chars.len());
+    }
+
+    fn line_start(&self) -> usize {
+        self.chars[..self.pos]
+            .iter()
+            .rposition(|&c| c == '\n')
+            .map_or(0, |i| i + 1)
+    }
+
+    fn line_end(&self) -> usize {
+        self.chars[self.pos..]
+            .iter()
+            .position(|&c| c == '\n')
+            .map_or(self.chars.len(), |i| self.pos + i)
+    }
+
+    pub fn home(&mut self) {
+        self.pos = self.line_start();
+    }
+
+    pub fn end(&mut self) {
+        self.pos = self.line_end();
+    }
+
+    /// Moves to the line above keeping the column, returns false on the first line.
+    pub fn up(&mut self) -> bool {
+        let start = self.line_start();
+        if start == 0 {
+            return false;
+        }
+        let col = self.pos - start;
+        self.pos = start - 1;
+        self.pos = std::cmp::min(self.line_start() + col, self.pos);
+        true
+    }
+
+    /// Moves to the line below keeping the column, returns false on the last line.
+    pub fn down(&mut self) -> bool {
+        let end = self.line_end();
+        if end == self.chars.len() {
+            return false;
+        }
+        let col = self.pos - self.line_start();
+        self.pos = end + 1;
+        self.pos = std::cmp::min(self.pos + col, self.line_end());
+        true
+    }
+
+    /// Breaks the line, continuing the Markdown list or quote it is in. On
+    /// an empty item the marker is dropped instead, which ends the list.
+    pub fn newline(&mut self) {
+        let start = self.line_start();
+        let line: String = self.chars[start..self.pos].iter().collect();
+        let (prefix, ended) = continuation(&line);
+        if let Some(ended) = ended {
+            self.chars.drain(start..self.pos);
+            self.pos = start;
+            self.insert_str(&ended);
+        } else {
+            self.insert('\n');
+            self.insert_str(&prefix);
+        }
+    }
+
+    pub fn word_left(&mut self) {
+        while self.pos > 0 && !self.chars[self.pos - 1].is_alphanumeric() {
+            self.pos -= 1;
+        }
+        while self.pos > 0 && self.chars[self.pos - 1].is_alphanumeric() {
+            self.pos -= 1;
+        }
+    }
+
+    pub fn word_right(&mut self) {
+        let len = self.chars.len();
+        while self.pos < len && !self.chars[self.pos].is_alphanumeric() {
+            self.pos += 1;
+        }
+        while self.pos < len && self.chars[self.pos].is_alphanumeric() {
+            self.pos += 1;
+        }
+    }
+
+    pub fn backspace(&mut self) {
+        if self.pos > 0 {
+            self.pos -= 1;
+            self.chars.remove(self.pos);
+        }
+    }
+
+    pub fn delete(&mut self) {
+        if self.pos < self.chars.len() {
+            self.chars.remove(self.pos);
+        }
+    }
+
+    pub fn delete_word_left(&mut self) {
+        let end = self.pos;
+        self.word_left();
+        self.chars.drain(self.pos..end);
+    }
+
+    pub fn delete_word_right(&mut self) {
+        let start = self.pos;
+        self.word_right();
+        self.chars.drain(start..self.pos);
+        self.pos = start;
+    }
+
+    pub fn kill_to_start(&mut self) {
+        let start = self.line_start();
+        self.chars.drain(start..self.pos);
+        self.pos = start;
+    }
+
+    pub fn kill_to_end(&mut self) {
+        let end = self.line_end();
+        self.chars.drain(self.pos..end);
+    }
+
+    /// Completes the word before the cursor, which is delimited by spaces,
+    /// `,` or `;`, to the longest prefix shared by the `candidates` starting
+    /// with it. Returns those candidates.
+    pub fn complete(&mut self, candidates: &[String]) -> Vec<String> {
+        let head = self.head();
+        let word = head
+            .rsplit(|c: char| c.is_whitespace() || c == ',' || c == ';')
+            .next()
+            .unwrap_or("");
+        let mut matches: Vec<String> = candidates
+            .iter()
+            .filter(|c| c.starts_with(word) && c.as_str() != word)
+            .cloned()
+            .collect();
+        matches.sort();
+        matches.dedup();
+        if let Some(first) = matches.first() {
+            let common = matches.iter().fold(first.as_str(), |common, m| {
+                let n = common
+                    .char_indices()
+                    .zip(m.chars())
+                    .find(|((_, a), b)| a != b)
+                    .map_or(common.len().min(m.len()), |((i, _), _)| i);
+                &common[..n]
+            });
+            self.insert_str(&common[word.len()..]);
+        }
+        matches
+    }
+}
+
+/// The prefix of the line after `line`: its indent, quote markers and list
+/// bullet (numbers counting up, task boxes unchecked). If `line` holds
+/// nothing but those, it is instead what `line` becomes with the innermost
+/// marker dropped.
+fn continuation(line: &str) -> (String, Option<String>) {
+    let mut rest = line.trim_start();
+    let mut prefix = line[..line.len() - rest.len()].to_owned();
+    while let Some(r) = rest.strip_prefix('>') {
+        let r = r.strip_prefix(' ').unwrap_or(r);
+        prefix += &rest[..rest.len() - r.len()];
+        rest = r;
+    }
+    let indent = line.len() - line.trim_start().len();
+    let marked = prefix.len();
+    let digits = rest.len() - rest.trim_start_matches(|c: char| c.is_ascii_digit()).len();
+    let bullet = if let Some(r) = ["- ", "* ", "+ "].iter().find_map(|b| rest.strip_prefix(b)) {
+        prefix += &rest[..2];
+        Some(r)
+    } else if digits > 0 && rest[digits..].starts_with(". ") {
+        let n: u64 = rest[..digits].parse().unwrap_or(0);
+        prefix += &format!("{}. ", n + 1);
+        Some(&rest[digits + 2..])
+    } else {
+        None
+    };
+    if let Some(r) = bullet {
+        rest = r;
+        let lower = rest.to_lowercase();
+        if lower.starts_with("[ ] ") || lower.starts_with("[x] ") {
+            prefix += "[ ] ";
+            rest = &rest[4..];
+        }
+    }
+    let ended = if !rest.trim().is_empty() {
+        None
+    } else if bullet.is_some() {
+        Some(prefix[..marked].to_owned())
+    } else if marked > indent {
+        Some(String::new())
+    } else {
+        None
+    };
+    (prefix, ended)
+}
+
+/// Recent answers of each prompt, newest last.
+#[derive(Serialize, Deserialize, Default, Clone)]
+pub struct History {
+    fields: BTreeMap<String, Vec<String>>,
+}
+
+impl History {
+    pub fn entries(&self, field: &str) -> &[String] {
+        self.fields.get(field).map_or(&[], |v| v.as_slice())
+    }
+
+    pub fn push(&mut self, field: &str, entry: &str) {
+        if entry.trim().is_empty() {
+            return;
+        }
+        let entries = self.fields.entry(field.to_owned()).or_default();
+        entries.retain(|e| e != entry);
+        entries.push(entry.to_owned());
+        if entries.len() > HISTORY_LIMIT {
+            entries.remove(0);
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    #[test]
+    fn test_basis() {
+        let mut line = LineBuffer::new("hello big world");
+        line.word_left();
+        line.delete_word_left();
+        assert_eq!(line.text(), "hello world");
+        line.home();
+        line.delete_word_right();
+        assert_eq!(line.text(), " world");
+        line.end();
+        line.kill_to_start();
+        line.insert_str("work, ur");
+        let labels = vec!["urgent".to_owned(), "urgent-ish".to_owned()];
+        assert_eq!(line.complete(&labels).len(), 2);
+        assert_eq!(line.text(), "work, urgent");
+
+        let mut text = LineBuffer::new("> 1. [x] done");
+        text.newline();
+        text.insert_str("next");
+        assert!(text.up());
+        text.newline();
+        assert_eq!(text.text(), "> 1. [x] done\n> 2. [ ] \n> 2. [ ] next");
+        text.newline();
+        assert_eq!(text.text(), "> 1. [x] done\n> \n> 2. [ ] next");
+        text.newline();
+        assert_eq!(text.text(), "> 1. [x] done\n\n> 2. [ ] next");
+        assert!(text.down());
+        text.end();
+        text.kill_to_start();
+        assert_eq!(text.text(), "> 1. [x] done\n\n");
+
+        let mut history = History::default();
+        history.push("summary", "a");
+        history.push("summary", "b");
+        history.push("summary", "a");
+        assert_eq!(history.entries("summary"), ["b", "a"]);
+        assert!(history.entries("detail").is_empty());
+    }
+}
@@ -0,0 +1,121 @@
+use crate::error::{Error, Result};
+use crate::status::Icons;
+use crossterm::style::{Attribute, Color, Color::*, ContentStyle};
+use termimad::MadSkin;
+
+pub const THEMES: &[&str; 4] = &["dark", "light", "high-contrast", "plain"];
+
+/// Styles of everything the interaction layer prints.
+#[derive(Clone)]
+pub struct Theme {
+    pub prompt: ContentStyle,
+    pub question: ContentStyle,
+    pub tips: ContentStyle,
+    /// The current row of pickers and the tui, and the tui header.
+    pub selected: ContentStyle,
+    pub help: ContentStyle,
+    pub search: ContentStyle,
+    pub skin: MadSkin,
+    pub icons: Icons,
+}
+
+fn fg(color: Color) -> ContentStyle {
+    ContentStyle::new().foreground(color)
+}
+
+impl Theme {
+    /// The theme called `name`, or `plain` whatever the name is when `color`
+    /// is off.
+    pub fn named(name: &str, color: bool) -> Result<Theme> {
+        if !THEMES.contains(&name) {
+            return Err(Error::StringError(format!(
+                "unknown theme `{}`, expect one of {}",
+                name,
+                THEMES.join(", ")
+            )));
+        }
+        if !color || name == "plain" {
+            return Ok(Theme::plain());
+        }
+        let mut skin = MadSkin::default();
+        skin.italic.add_attr(Attribute::Underlined);
+        let theme = match name {
+            "dark" => {
+                skin.bold.set_fg(DarkRed);
+                skin.paragraph.set_fg(Blue);
+                skin.headers.iter_mut().for_each(|h| h.set_fg(Yellow));
+                Theme {
+                    prompt: fg(Green),
+                    question: fg(White),
+                    tips: fg(Blue),
+                    selected: fg(Black).background(Yellow),
+                    help: fg(DarkGrey),
+                    search: fg(DarkBlue),
+                    skin,
+                    icons: Icons::default(),
+                }
+            }
+            "light" => {
+                skin.bold.set_fg(DarkRed);
+                skin.paragraph.set_fg(DarkBlue);
+                skin.headers.iter_mut().for_each(|h| h.set_fg(DarkMagenta));
+                Theme {
+                    prompt: fg(DarkGreen),
+                    question: fg(Black),
+                    tips: fg(DarkBlue),
+                    selected: fg(White).background(DarkBlue),
+                    help: fg(DarkGrey),
+                    search: fg(DarkMagenta),
+                    skin,
+                    icons: Icons::default(),
+                }
+            }
+            // high-contrast, the only name left
+            _ => {
+                skin.bold.set_fg(Yellow);
+                skin.paragraph.set_fg(White);
+                skin.headers.iter_mut().for_each(|h| h.set_fg(Cyan));
+                let bold = |color| fg(color).attribute(Attribute::Bold);
+                Theme {
+                    prompt: bold(Yellow),
+                    question: bold(White),
+                    tips: fg(Cyan),
+                    selected: bold(Black).background(White),
+                    help: fg(White),
+                    search: bold(Yellow),
+                    skin,
+                    icons: Icons::default(),
+                }
+            }
+        };
+        Ok(theme)
+    }
+
+    /// No colors at all, the current row is shown reversed.
+    pub fn plain() -> Theme {
+        Theme {
+            prompt: ContentStyle::new(),
+            question: ContentStyle::new(),
+            tips: ContentStyle::new(),
+            selected: ContentStyle::new().attribute(Attribute::Reverse),
+            help: ContentStyle::new(),
+            search: ContentStyle::new(),
+            skin: MadSkin::no_style(),
+            icons: Icons::default(),
+        }
+    }
+}
+
+impl Default for Theme {
+    fn default() -> Theme {
+        Theme::named(THEMES[0], true).unwrap()
+    }
+}
+
+/// Whether colors may be printed: `NO_COLOR` isn't set and stdout is a
+/// terminal that isn't dumb.
+pub fn color_enabled() -> bool {
+    std::env::var_os("NO_COLOR").is_none_or(|v| v.is_empty())
+        && std::env::var("TERM").map_or(true, |term| term != "dumb")
+        && atty::is(atty::Stream::Stdout)
+}
@@ -0,0 +1,345 @@
+use super::icore::Searchable;
+use super::ievent::EventSource;
+use super::iview::{PageView, View};
+use super::iwidth::truncate;
+use crate::{
+    clipboard::{Clip, CLIP_HELP},
+    error::Error,
+    filter::{Filter, Sort},
+    item::Flag,
+    status::TALL,
+    store::ItemPair,
+    Result,
+};
+use crossterm::{cursor, event, execute, queue, style, terminal};
+use std::io::{stdout, Write};
+use std::time::{Duration, Instant};
+use termimad::Area;
+
+const DOUBLE_CLICK: Duration = Duration::from_millis(400);
+
+static TUI_HELP: &str =
+    "j/k move, / filter, enter view, a add, e edit, d done, x delete, y copy, p pin, A archive, t tag, s sort, q quit";
+
+/// What the user asked for from the full-screen interface.
+pub enum TuiOp {
+    Quit,
+    Add,
+    View(ItemPair),
+    Edit(ItemPair),
+    Toggle(ItemPair),
+    Delete(ItemPair),
+    Flag(ItemPair, Flag),
+}
+
+/// A persistent list/preview interface living in the alternate screen,
+/// which is left when this is dropped.
+pub struct Tui {
+    view: View,
+    filter: String,
+    filtering: bool,
+    tag: Option<&'static str>,
+    sort: Sort,
+    cur: usize,
+    top: usize,
+    message: String,
+}
+
+impl Tui {
+    pub fn new(view: View) -> Tui {
+        Tui {
+            view,
+            filter: String::new(),
+            filtering: false,
+            tag: None,
+            sort: Sort::Time,
+            cur: 0,
+            top: 0,
+            message: String::new(),
+        }
+    }
+
+    /// Shows `message` in the status bar, or the key help if it is empty.
+    pub fn message(&mut self, message: impl Into<String>) {
+        self.message = message.into();
+    }
+
+    fn visible(&self, items: Vec<ItemPair>) -> Result<Vec<ItemPair>> {
+        let filter = Filter {
+            tags: self.tag.iter().map(|&tag| tag.to_owned()).collect(),
+            text: self.filter.clone(),
+            sort: self.sort,
+            ..Filter::default()
+        };
+        filter.apply(items)
+    }
+
+    /// Runs on `events` until the user picks an action that needs the store
+    /// or prompts, the screen is left cleared and in cooked mode for those
+    /// prompts.
+    pub fn run(&mut self, items: Vec<ItemPair>, events: &mut dyn EventSource) -> Result<TuiOp> {
+        let mut w = stdout();
+        let tty = events.is_terminal();
+        execute!(w, terminal::EnterAlternateScreen, cursor::Hide)?;
+        if tty {
+            terminal::enable_raw_mode()?;
+            if self.view.mouse() {
+                execute!(w, event::EnableMouseCapture)?;
+            }
+        }
+        let op = self.event_loop(items, events);
+        if tty {
+            if self.view.mouse() {
+                execute!(w, event::DisableMouseCapture)?;
+            }
+            terminal::disable_raw_mode()?;
+        }
+        execute!(
+            w,
+            terminal::Clear(terminal::ClearType::All),
+            cursor::MoveTo(0, 0),
+            cursor::Show
+        )?;
+        op
+    }
+
+    fn event_loop(&mut self, items: Vec<ItemPair>, events: &mut dyn EventSource) -> Result<TuiOp> {
+        let mut list = self.visible(items.clone())?;
+        let mut last_click: Option<(usize, Instant)> = None;
+        let mut copying = false;
+        loop {
+            let (width, height) = events.size()?;
+            let rows = height.saturating_sub(2) as usize;
+            self.cur = std::cmp::min(self.cur, list.len().saturating_sub(1));
+            if self.cur < self.top {
+                self.top = self.cur;
+            } else if rows > 0 && self.cur >= self.top + rows {
+                self.top = self.cur + 1 - rows;
+            }
+            self.draw(&list, (width, height))?;
+
+            let key = match events.read()? {
+                event::Event::Key(key) => key,
+                event::Event::Mouse(m) => {
+                    match m.kind {
+                        event::MouseEventKind::ScrollDown => {
+                            self.cur = std::cmp::min(self.cur + 1, list.len().saturating_sub(1))
+                        }
+                        event::MouseEventKind::ScrollUp => self.cur = self.cur.saturating_sub(1),
+                        event::MouseEventKind::Down(event::MouseButton::Left)
+                            if m.column < list_width(width)
+                                && m.row >= 1
+                                && (m.row as usize) <= rows
+                                && self.top + (m.row as usize) <= list.len() =>
+                        {
+                            let row = self.top + m.row as usize - 1;
+                            let double = last_click.is_some_and(|(last, at)| {
+                                last == row && at.elapsed() < DOUBLE_CLICK
+                            });
+                            last_click = Some((row, Instant::now()));
+                            self.cur = row;
+                            if double {
+                                return Ok(TuiOp::View(list[row].clone()));
+                            }
+                        }
+                        _ => {}
+                    }
+                    continue;
+                }
+                _ => continue,
+            };
+            if key.code == event::KeyCode::Char('c')
+                && key.modifiers.contains(event::KeyModifiers::CONTROL)
+            {
+                return Err(Error::StringError("Terminate by Ctrl-C".to_owned()));
+            }
+            if self.filtering {
+                match key.code {
+                    event::KeyCode::Enter => self.filtering = false,
+                    event::KeyCode::Esc => {
+                        self.filtering = false;
+                        self.filter.clear();
+                    }
+                    event::KeyCode::Backspace => {
+                        self.filter.pop();
+                    }
+                    event::KeyCode::Char(c) => self.filter.push(c),
+                    _ => continue,
+                }
+                self.cur = 0;
+                list = self.visible(items.clone())?;
+                continue;
+            }
+            let selected = list.get(self.cur).cloned();
+            if copying {
+                copying = false;
+                self.message.clear();
+                if let (Some(pair), event::KeyCode::Char(c)) = (&selected, key.code) {
+                    if let Some(part) = Clip::from_key(c) {
+                        self.message = self.view.copy(&pair.item, part);
+                    }
+                }
+                continue;
+            }
+            match key.code {
+                event::KeyCode::Char('q') | event::KeyCode::Esc => return Ok(TuiOp::Quit),
+                event::KeyCode::Char('j') | event::KeyCode::Down => {
+                    self.cur = std::cmp::min(self.cur + 1, list.len().saturating_sub(1))
+                }
+                event::KeyCode::Char('k') | event::KeyCode::Up => {
+                    self.cur = self.cur.saturating_sub(1)
+                }
+                event::KeyCode::PageDown => self.cur += rows,
+                event::KeyCode::PageUp => self.cur = self.cur.saturating_sub(rows),
+                event::KeyCode::Char('g') | event::KeyCode::Home => self.cur = 0,
+                event::KeyCode::Char('G') | event::KeyCode::End => {
+                    self.cur = list.len().saturating_sub(1)
+                }
+                event::KeyCode::Char('/') => {
+                    self.filtering = true;
+                    self.filter.clear();
+                }
+                event::KeyCode::Char('t') => {
+                    self.tag = match self.tag {
+                        None => Some(TALL[0]),
+                        Some(tag) => TALL
+                            .iter()
+                            .position(|t| *t == tag)
+                            .and_then(|i| TALL.get(i + 1))
+                            .copied(),
+                    };
+                    self.cur = 0;
+                }
+                event::KeyCode::Char('s') => self.sort = self.sort.next(),
+                event::KeyCode::Char('a') => return Ok(TuiOp::Add),
+                event::KeyCode::Enter => {
+                    if let Some(pair) = selected {
+                        return Ok(TuiOp::View(pair));
+                    }
+                }
+                event::KeyCode::Char('e') => {
+                    if let Some(pair) = selected {
+                        return Ok(TuiOp::Edit(pair));
+                    }
+                }
+                event::KeyCode::Char('d') => {
+                    if let Some(pair) = selected {
+                        return Ok(TuiOp::Toggle(pair));
+                    }
+                }
+                event::KeyCode::Char('x') | event::KeyCode::Delete => {
+                    if let Some(pair) = selected {
+                        return Ok(TuiOp::Delete(pair));
+                    }
+                }
+                event::KeyCode::Char('p') => {
+                    if let Some(pair) = selected {
+                        return Ok(TuiOp::Flag(pair, Flag::Pinned));
+                    }
+                }
+                event::KeyCode::Char('A') => {
+                    if let Some(pair) = selected {
+                        return Ok(TuiOp::Flag(pair, Flag::Archived));
+                    }
+                }
+                event::KeyCode::Char('y') if selected.is_some() => {
+                    copying = true;
+                    self.message = CLIP_HELP.to_owned();
+                }
+                _ => continue,
+            }
+            list = self.visible(items.clone())?;
+        }
+    }
+
+    fn draw(&self, list: &[ItemPair], (width, height): (u16, u16)) -> Result<()> {
+        let mut w = stdout();
+        let list_width = list_width(width);
+        let theme = self.view.theme();
+        let separator = theme.icons.choose("│", "|");
+        let rows = height.saturating_sub(2) as usize;
+        queue!(
+            w,
+            terminal::Clear(terminal::ClearType::All),
+            cursor::MoveTo(0, 0)
+        )?;
+
+        let header = format!(
+            " {} ideas | tag: {} | sort: {} | filter: {}",
+            list.len(),
+            self.tag.unwrap_or("all"),
+            self.sort.name(),
+            self.filter
+        );
+        queue!(
+            w,
+            style::PrintStyledContent(theme.selected.apply(truncate(&header, width as usize)))
+        )?;
+
+        for (row, pair) in list.iter().skip(self.top).take(rows).enumerate() {
+            let line = truncate(
+                &format!("{:>4} {}", pair.id, pair.item.line(theme.icons)),
+                (list_width as usize).saturating_sub(1),
+            );
+            queue!(w, cursor::MoveTo(0, row as u16 + 1))?;
+            if self.top + row == self.cur {
+                queue!(w, style::PrintStyledContent(theme.selected.apply(line)))?;
+            } else {
+                queue!(w, style::Print(line))?;
+            }
+        }
+        for row in 1..height.saturating_sub(1) {
+            queue!(
+                w,
+                cursor::MoveTo(list_width, row),
+                style::PrintStyledContent(theme.help.apply(separator))
+            )?;
+        }
+        if let Some(pair) = list.get(self.cur).filter(|_| width > list_width + 2) {
+            let area = Area::new(
+                list_width + 1,
+                1,
+                width.saturating_sub(list_width + 1),
+                height.saturating_sub(2),
+            );
+            PageView::from(
+                self.view.page(&pair.item, &[]),
+                area,
+                self.view.skin().clone(),
+            )
+            .write_on(&mut w)?;
+        }
+
+        let status = if self.filtering {
+            theme.search.apply(format!("/{}", self.filter))
+        } else {
+            let message = if self.message.is_empty() {
+                TUI_HELP
+            } else {
+                &self.message
+            };
+            theme.help.apply(truncate(message, width as usize))
+        };
+        queue!(
+            w,
+            cursor::MoveTo(0, height.saturating_sub(1)),
+            style::PrintStyledContent(status)
+        )?;
+        w.flush()?;
+        Ok(())
+    }
+}
+
+impl Drop for Tui {
+    fn drop(&mut self) {
+        if self.view.mouse() {
+            let _ = execute!(stdout(), event::DisableMouseCapture);
+        }
+        let _ = terminal::disable_raw_mode();
+        let _ = execute!(stdout(), cursor::Show, terminal::LeaveAlternateScreen);
+    }
+}
+
+fn list_width(width: u16) -> u16 {
+    std::cmp::max(width * 2 / 5, std::cmp::min(width, 24))
+}
@@ -1,6 +1,12 @@
-use crate::item::Item;
-use crossterm::style::Color::*;
-use minimad::{OwningTemplateExpander, TextTemplate};
+use super::icore::Searchable;
+use super::ievent::EventSource;
+use super::itheme::Theme;
+use crate::clipboard::{Clip, Clipboard, CLIP_HELP};
+use crate::{error::Error, item::Item, store::ItemPair, Result};
+use crossterm::{cursor, event, execute, queue, style, terminal};
+use defer::defer;
+use minimad::{OwningTemplateExpander, Text, TextTemplate};
+use std::io::{stdout, Write};
 use termimad::*;
 
 static SUMMARY_TEMPLATE: &str = r#"
@@ -18,50 +24,198 @@ ${detail}
 --------------------
 "#;
 
+static LABEL_TEMPLATE: &str = r#"
+## Labels
+* ${label}
+--------------------
+"#;
+
+static CHECKLIST_TEMPLATE: &str = r#"
+## Checklist ${progress}
+"#;
+
+static DONE_TASK_TEMPLATE: &str = "* [x] ~~${task}~~";
+
+static TODO_TASK_TEMPLATE: &str = "* [ ] ${task}";
+
+static SEPARATOR_TEMPLATE: &str = "--------------------";
+
+static SCHEDULE_TEMPLATE: &str = r#"
+## Schedule
+* priority: ${priority}
+* due: ${due}
+* repeat: ${recur}
+--------------------
+"#;
+
+static LINK_TEMPLATE: &str = r#"
+## Links
+${link
+* *${relation}*: ${item} (#${id})
+}
+--------------------
+"#;
+
+static ATTACHMENT_TEMPLATE: &str = r#"
+## Attachments
+${attachment
+${n}. ${name} `${hash}`
+}
+--------------------
+"#;
+
 static URL_TEMPLATE: &str = r#"
 ## Reference
-${url}
+* ${url}
 --------------------
 "#;
 
+static PAGER_HELP: &str =
+    "j/k scroll, g/G top/bottom, / search, n/N next/prev, e edit, t toggle, x delete, y copy, q quit";
+
+/// Lines scrolled by a notch of the mouse wheel.
+pub const WHEEL_LINES: i32 = 3;
+
+/// What the user asked for from the detail viewer.
+#[derive(Clone, Debug, PartialEq)]
+pub enum ViewOp {
+    Quit,
+    Edit,
+    Toggle,
+    Delete,
+}
+
+#[derive(Clone)]
 pub struct View {
-    summary: TextTemplate<'static>,
-    detail: TextTemplate<'static>,
-    url: TextTemplate<'static>,
+    theme: Theme,
+    mouse: bool,
+    clipboard: Clipboard,
+}
+
+/// An item filled into the templates of the sections it has.
+#[derive(Default)]
+pub struct Page {
+    sections: Vec<(&'static str, OwningTemplateExpander<'static>)>,
+}
+
+impl Page {
+    /// Adds a section, filled through the returned expander.
+    fn section(&mut self, template: &'static str) -> &mut OwningTemplateExpander<'static> {
+        self.sections
+            .push((template, OwningTemplateExpander::new()));
+        &mut self.sections.last_mut().unwrap().1
+    }
+
+    pub fn text(&self) -> Text<'_> {
+        let lines = self
+            .sections
+            .iter()
+            .flat_map(|(template, expander)| expander.expand(&TextTemplate::from(*template)).lines)
+            .collect();
+        Text { lines }
+    }
+
+    /// Renders the page, wrapped at `width` if any.
+    pub fn render(&self, skin: &MadSkin, width: Option<usize>) -> String {
+        FmtText::from_text(skin, self.text(), width).to_string()
+    }
+}
+
+/// A scrollable `Page` in an area, like `MadView` is for markdown.
+pub struct PageView {
+    page: Page,
+    area: Area,
     skin: MadSkin,
+    pub scroll: i32,
+}
+
+impl PageView {
+    pub fn from(page: Page, area: Area, skin: MadSkin) -> PageView {
+        PageView {
+            page,
+            area,
+            skin,
+            scroll: 0,
+        }
+    }
+
+    fn text(&self) -> FmtText<'_, '_> {
+        let width = self.area.width.saturating_sub(1) as usize;
+        FmtText::from_text(&self.skin, self.page.text(), Some(width))
+    }
+
+    pub fn write_on<W: Write>(&self, w: &mut W) -> Result<()> {
+        let text = self.text();
+        let mut view = TextView::from(&self.area, &text);
+        view.scroll = self.scroll;
+        view.write_on(w)
+            .map_err(|e| Error::StringError(e.to_string()))?;
+        Ok(w.flush()?)
+    }
+
+    /// Keeps the scroll, unless the width changed and the lines wrap anew.
+    pub fn resize(&mut self, area: Area) {
+        if area.width != self.area.width {
+            self.scroll = 0;
+        }
+        self.area = area;
+    }
+
+    pub fn try_scroll_lines(&mut self, lines_count: i32) {
+        let text = self.text();
+        let mut view = TextView::from(&self.area, &text);
+        view.scroll = self.scroll;
+        self.scroll = view.try_scroll_lines(lines_count);
+    }
+
+    pub fn try_scroll_pages(&mut self, pages_count: i32) {
+        self.try_scroll_lines(pages_count * i32::from(self.area.height));
+    }
 }
 
 impl View {
     pub fn new() -> View {
-        let mut skin = MadSkin::default();
-        skin.bold.set_fg(DarkRed);
-        skin.paragraph.set_fg(Blue);
-        skin.italic
-            .add_attr(crossterm::style::Attribute::Underlined);
-        for header in skin.headers.iter_mut() {
-            header.set_fg(Yellow);
-        }
         View {
-            summary: TextTemplate::from(SUMMARY_TEMPLATE),
-            detail: TextTemplate::from(DETAIL_TEMPLATE),
-            url: TextTemplate::from(URL_TEMPLATE),
-            skin,
+            theme: Theme::default(),
+            mouse: false,
+            clipboard: Clipboard::default(),
         }
     }
 
-    pub fn run(&mut self, item: Item) {
-        let (w, _) = terminal_size();
-        let mut expander = OwningTemplateExpander::new();
-        let mut expander_d = OwningTemplateExpander::new();
-        let mut expander_u = OwningTemplateExpander::new();
-        expander
-            .set("summary", item.summary)
+    pub fn theme(&self) -> &Theme {
+        &self.theme
+    }
+
+    pub fn set_theme(&mut self, theme: Theme) {
+        self.theme = theme;
+    }
+
+    pub fn skin(&self) -> &MadSkin {
+        &self.theme.skin
+    }
+
+    pub fn mouse(&self) -> bool {
+        self.mouse
+    }
+
+    pub fn set_mouse(&mut self, mouse: bool) {
+        self.mouse = mouse;
+    }
+
+    pub fn set_clipboard(&mut self, clipboard: Clipboard) {
+        self.clipboard = clipboard;
+    }
+
+    /// Fills the sections `item` has, user text is set as is, only the
+    /// detail is read as Markdown.
+    pub fn page(&self, item: &Item, related: &[(String, ItemPair)]) -> Page {
+        let mut page = Page::default();
+        page.section(SUMMARY_TEMPLATE)
             .set("ty", item.ty.desc())
-            .set("status", item.ty.status());
-        let summary_str =
-            FmtText::from_text(&self.skin, expander.expand(&self.summary), Some(w as usize));
-        let detail_str = if let Some(detail) = item.detail {
-            let lines: Vec<String> = detail
+            .set("status", self.theme.icons.icon(item.ty.status()))
+            .set("summary", &item.summary);
+        if let Some(ref detail) = item.detail {
+            let detail: String = detail
                 .lines()
                 .map(|x| {
                     if x.starts_with("> ") {
@@ -71,33 +225,254 @@ impl View {
                     }
                 })
                 .collect();
-            let detail: String = lines.iter().flat_map(|s| s.chars()).collect();
-            expander_d.set_lines_md("detail", detail);
-            FmtText::from_text(
-                &self.skin,
-                expander_d.expand(&self.detail),
-                Some(w as usize),
-            )
-        } else {
-            FmtText::from(&self.skin, "", Some(w as usize))
-        };
-        let url_str = if let Some(url) = item.url {
-            let lines: Vec<String> = url
-                .lines()
-                .map(|x| {
-                    if x.starts_with("* ") {
-                        format!("{}  \n", x)
-                    } else {
-                        format!("* *{}*  \n", x)
+            page.section(DETAIL_TEMPLATE).set_lines_md("detail", detail);
+        }
+        if !item.labels.is_empty() {
+            page.section(LABEL_TEMPLATE)
+                .set_lines("label", item.labels.join("\n"));
+        }
+        if let Some((done, total)) = item.progress() {
+            page.section(CHECKLIST_TEMPLATE)
+                .set("progress", format!("{}/{}", done, total));
+            for t in &item.subtasks {
+                let template = if t.done {
+                    DONE_TASK_TEMPLATE
+                } else {
+                    TODO_TASK_TEMPLATE
+                };
+                page.section(template).set("task", &t.summary);
+            }
+            page.section(SEPARATOR_TEMPLATE);
+        }
+        if item.due.is_some() || item.recur.is_some() || item.priority.is_some() {
+            let show = |x: Option<String>| x.unwrap_or_else(|| "-".to_owned());
+            page.section(SCHEDULE_TEMPLATE)
+                .set("priority", show(item.priority.map(|p| p.to_string())))
+                .set("due", show(item.due.map(|d| d.to_string())))
+                .set("recur", show(item.recur.as_ref().map(|r| r.to_string())));
+        }
+        if !related.is_empty() {
+            let links = page.section(LINK_TEMPLATE);
+            for (rel, pair) in related {
+                links
+                    .sub("link")
+                    .set("relation", rel)
+                    .set("item", pair.item.line(self.theme.icons))
+                    .set("id", &pair.id);
+            }
+        }
+        if !item.attachments.is_empty() {
+            let attachments = page.section(ATTACHMENT_TEMPLATE);
+            for (i, a) in item.attachments.iter().enumerate() {
+                attachments
+                    .sub("attachment")
+                    .set("n", i + 1)
+                    .set("name", a)
+                    .set("hash", &a.hash[..8]);
+            }
+        }
+        if !item.urls.is_empty() {
+            let urls: Vec<_> = item.urls.iter().map(|r| r.to_string()).collect();
+            page.section(URL_TEMPLATE).set_lines("url", urls.join("\n"));
+        }
+        page
+    }
+
+    /// Shows the item in a full-screen pager reading `events`, or just
+    /// prints it when the output is not a terminal.
+    pub fn run(
+        &mut self,
+        item: Item,
+        related: Vec<(String, ItemPair)>,
+        events: &mut dyn EventSource,
+    ) -> Result<ViewOp> {
+        let page = self.page(&item, &related);
+        if !atty::is(atty::Stream::Stdout) {
+            let (width, _) = terminal_size();
+            println!("{}", page.render(self.skin(), Some(width as usize)));
+            return Ok(ViewOp::Quit);
+        }
+        self.pager(&item, page, events)
+    }
+
+    fn pager(&mut self, item: &Item, page: Page, events: &mut dyn EventSource) -> Result<ViewOp> {
+        let mut w = stdout();
+        let area = |(width, height): (u16, u16)| Area::new(0, 0, width, height.saturating_sub(1));
+        execute!(w, terminal::EnterAlternateScreen, cursor::Hide)?;
+        let tty = events.is_terminal();
+        let mouse = self.mouse && tty;
+        if tty {
+            terminal::enable_raw_mode()?;
+        }
+        if mouse {
+            execute!(w, event::EnableMouseCapture)?;
+        }
+        let _reset = defer(|| {
+            if mouse {
+                execute!(stdout(), event::DisableMouseCapture).unwrap();
+            }
+            if tty {
+                terminal::disable_raw_mode().unwrap();
+            }
+            execute!(stdout(), cursor::Show, terminal::LeaveAlternateScreen).unwrap();
+        });
+
+        let mut view = PageView::from(page, area(events.size()?), self.skin().clone());
+        let mut pattern = String::new();
+        let mut searching = false;
+        let mut copying = false;
+        let mut status = PAGER_HELP.to_owned();
+        loop {
+            view.write_on(&mut w)?;
+            let (_, height) = events.size()?;
+            let line = if searching {
+                self.theme.search.apply(format!("/{}", pattern))
+            } else {
+                self.theme.help.apply(status.clone())
+            };
+            queue!(
+                w,
+                cursor::MoveTo(0, height.saturating_sub(1)),
+                terminal::Clear(terminal::ClearType::CurrentLine),
+                style::PrintStyledContent(line)
+            )?;
+            w.flush()?;
+
+            let key = match events.read()? {
+                event::Event::Key(key) => key,
+                event::Event::Resize(..) => {
+                    view.resize(area(events.size()?));
+                    continue;
+                }
+                event::Event::Mouse(m) => {
+                    match m.kind {
+                        event::MouseEventKind::ScrollDown => view.try_scroll_lines(WHEEL_LINES),
+                        event::MouseEventKind::ScrollUp => view.try_scroll_lines(-WHEEL_LINES),
+                        _ => {}
                     }
-                })
-                .collect();
-            let url: String = lines.iter().flat_map(|s| s.chars()).collect();
-            expander_u.set_lines_md("url", url);
-            FmtText::from_text(&self.skin, expander_u.expand(&self.url), Some(w as usize))
+                    continue;
+                }
+            };
+            if key.code == event::KeyCode::Char('c')
+                && key.modifiers.contains(event::KeyModifiers::CONTROL)
+            {
+                return Err(Error::StringError("Terminate by Ctrl-C".to_owned()));
+            }
+            if copying {
+                copying = false;
+                status = PAGER_HELP.to_owned();
+                if let event::KeyCode::Char(c) = key.code {
+                    if let Some(part) = Clip::from_key(c) {
+                        status = self.copy(item, part);
+                    }
+                }
+                continue;
+            }
+            if searching {
+                match key.code {
+                    event::KeyCode::Enter => {
+                        searching = false;
+                        status = self.search(&mut view, &pattern, true, false);
+                    }
+                    event::KeyCode::Esc => {
+                        searching = false;
+                        pattern.clear();
+                    }
+                    event::KeyCode::Backspace => {
+                        pattern.pop();
+                    }
+                    event::KeyCode::Char(c) => pattern.push(c),
+                    _ => {}
+                }
+                continue;
+            }
+            match key.code {
+                event::KeyCode::Char('j') | event::KeyCode::Down | event::KeyCode::Enter => {
+                    view.try_scroll_lines(1)
+                }
+                event::KeyCode::Char('k') | event::KeyCode::Up => view.try_scroll_lines(-1),
+                event::KeyCode::PageDown | event::KeyCode::Char(' ') => view.try_scroll_pages(1),
+                event::KeyCode::PageUp | event::KeyCode::Char('b') => view.try_scroll_pages(-1),
+                event::KeyCode::Char('g') | event::KeyCode::Home => view.scroll = 0,
+                event::KeyCode::Char('G') | event::KeyCode::End => {
+                    view.try_scroll_lines(i32::MAX / 2)
+                }
+                event::KeyCode::Char('/') => {
+                    searching = true;
+                    pattern.clear();
+                }
+                event::KeyCode::Char('n') => status = self.search(&mut view, &pattern, true, true),
+                event::KeyCode::Char('N') => status = self.search(&mut view, &pattern, false, true),
+                event::KeyCode::Char('y') => {
+                    copying = true;
+                    status = CLIP_HELP.to_owned();
+                }
+                event::KeyCode::Char('e') => return Ok(ViewOp::Edit),
+                event::KeyCode::Char('t') => return Ok(ViewOp::Toggle),
+                event::KeyCode::Char('x') | event::KeyCode::Delete => return Ok(ViewOp::Delete),
+                event::KeyCode::Char('q') | event::KeyCode::Esc => return Ok(ViewOp::Quit),
+                _ => {}
+            }
+        }
+    }
+
+    /// Copies `part` of `item`, returns the message for the status bar.
+    pub fn copy(&self, item: &Item, part: Clip) -> String {
+        self.clipboard.copy_part(part, item.clip(part))
+    }
+
+    /// Scrolls to the next (or previous) line matching `pattern`, returns
+    /// the message for the status bar.
+    fn search(
+        &self,
+        view: &mut PageView,
+        pattern: &str,
+        forward: bool,
+        skip_current: bool,
+    ) -> String {
+        if pattern.is_empty() {
+            return PAGER_HELP.to_owned();
+        }
+        let text = view.text();
+        let pattern = pattern.to_lowercase();
+        let matches: Vec<i32> = text
+            .lines
+            .iter()
+            .enumerate()
+            .filter(|(_, line)| match line {
+                FmtLine::Normal(fc) => fc
+                    .composite
+                    .compounds
+                    .iter()
+                    .map(|c| c.src)
+                    .collect::<String>()
+                    .to_lowercase()
+                    .contains(&pattern),
+                _ => false,
+            })
+            .map(|(i, _)| i as i32)
+            .collect();
+        let cur = view.scroll + if skip_current { 1 } else { 0 };
+        let found = if forward {
+            matches
+                .iter()
+                .find(|&&i| i >= cur)
+                .or_else(|| matches.first())
         } else {
-            FmtText::from(&self.skin, "", Some(w as usize))
+            matches
+                .iter()
+                .rev()
+                .find(|&&i| i < view.scroll)
+                .or_else(|| matches.last())
         };
-        println!("{}{}{}", summary_str, detail_str, url_str);
+        match found {
+            Some(&i) => {
+                view.scroll = 0;
+                view.try_scroll_lines(i);
+                let n = matches.iter().position(|&m| m == i).unwrap() + 1;
+                format!("/{} ({}/{})", pattern, n, matches.len())
+            }
+            None => format!("/{}: pattern not found", pattern),
+        }
     }
 }
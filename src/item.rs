@@ -1,30 +1,407 @@
-use crate::{interaction::Searchable, status::*};
+use crate::{
+    attachment::Attachment,
+    clipboard::Clip,
+    date::Date,
+    interaction::Searchable,
+    link::{Link, Relation},
+    recur::Recurrence,
+    reference::{deserialize_urls, Reference},
+    status::*,
+};
 use serde::{Deserialize, Serialize};
 use std::fmt::{Display, Formatter, Result};
+use std::str::FromStr;
+
+pub const PRIORITIES: &[&str; 3] = &["high", "medium", "low"];
 
 #[derive(Serialize, Deserialize, Clone)]
 pub struct Item {
     pub ty: Type,
     pub summary: String,
     pub detail: Option<String>,
-    pub url: Option<String>,
+    #[serde(default, alias = "url", deserialize_with = "deserialize_urls")]
+    pub urls: Vec<Reference>,
     pub time: std::time::SystemTime,
+    #[serde(default)]
+    pub due: Option<Date>,
+    #[serde(default)]
+    pub recur: Option<Recurrence>,
+    #[serde(default)]
+    pub subtasks: Vec<Subtask>,
+    #[serde(default)]
+    pub links: Vec<Link>,
+    #[serde(default)]
+    pub attachments: Vec<Attachment>,
+    #[serde(default)]
+    pub labels: Vec<String>,
+    /// Unknown for items captured before it was recorded.
+    #[serde(default)]
+    pub created: Option<std::time::SystemTime>,
+    /// When a todo got done, see `stamp_completion`.
+    #[serde(default)]
+    pub completed: Option<std::time::SystemTime>,
+    /// Whether `check` got the todo done, so unchecking a subtask undoes it.
+    #[serde(default)]
+    pub auto_completed: bool,
+    /// Kept at the top of listings.
+    #[serde(default)]
+    pub pinned: bool,
+    /// Hidden from listings unless asked for.
+    #[serde(default)]
+    pub archived: bool,
+    #[serde(default)]
+    pub priority: Option<Priority>,
+}
+
+/// How urgent an item is, `--sort priority` puts the highest first.
+#[derive(Serialize, Deserialize, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Debug)]
+#[serde(rename_all = "lowercase")]
+pub enum Priority {
+    Low,
+    Medium,
+    High,
+}
+
+/// A flag the picker and the `pin`/`archive` commands set on an item.
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum Flag {
+    Pinned,
+    Archived,
+}
+
+impl Flag {
+    pub fn name(self) -> &'static str {
+        match self {
+            Flag::Pinned => "pinned",
+            Flag::Archived => "archived",
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize, Clone)]
+pub struct Subtask {
+    pub summary: String,
+    pub done: bool,
+}
+
+impl Item {
+    pub fn new(ty: Type, summary: String) -> Item {
+        Item {
+            ty,
+            summary,
+            detail: None,
+            urls: vec![],
+            time: std::time::SystemTime::now(),
+            due: None,
+            recur: None,
+            subtasks: vec![],
+            links: vec![],
+            attachments: vec![],
+            labels: vec![],
+            created: Some(std::time::SystemTime::now()),
+            completed: None,
+            auto_completed: false,
+            pinned: false,
+            archived: false,
+            priority: None,
+        }
+    }
+
+    /// When the item was captured, its last update for older items.
+    pub fn created_at(&self) -> std::time::SystemTime {
+        self.created.unwrap_or(self.time)
+    }
+
+    /// When a done todo got done, its last update for older items.
+    pub fn completed_at(&self) -> Option<std::time::SystemTime> {
+        match self.ty.status() {
+            DONE => Some(self.completed.unwrap_or(self.time)),
+            _ => None,
+        }
+    }
+
+    /// Whether `flag` is set on the item.
+    pub fn flag(&self, flag: Flag) -> bool {
+        match flag {
+            Flag::Pinned => self.pinned,
+            Flag::Archived => self.archived,
+        }
+    }
+
+    pub fn set_flag(&mut self, flag: Flag, on: bool) {
+        match flag {
+            Flag::Pinned => self.pinned = on,
+            Flag::Archived => self.archived = on,
+        }
+    }
+
+    /// Records the time a todo gets done, and forgets it once undone.
+    pub fn stamp_completion(&mut self) {
+        if self.ty.status() != DONE {
+            self.completed = None;
+            self.auto_completed = false;
+        } else if self.completed.is_none() {
+            self.completed = Some(std::time::SystemTime::now());
+        }
+    }
+
+    /// The next instance of a recurring todo, if `self` completes `prev`.
+    /// The recurrence moves over to it, so `self` won't spawn another one
+    /// when it gets done again.
+    pub fn next_occurrence(&mut self, prev: &Item) -> Option<Item> {
+        if prev.ty.next_status() != Some(self.ty.status()) {
+            return None;
+        }
+        let recur = self.recur.take()?;
+        let mut item = self.clone();
+        item.ty = Type::new(UNDONE);
+        item.subtasks.iter_mut().for_each(|t| t.done = false);
+        item.due = Some(recur.upcoming(self.due.unwrap_or_else(Date::today)));
+        item.recur = Some(recur);
+        item.time = std::time::SystemTime::now();
+        item.created = Some(item.time);
+        item.completed = None;
+        Some(item)
+    }
+
+    /// Counts of (done, total) subtasks, if there is any.
+    pub fn progress(&self) -> Option<(usize, usize)> {
+        if self.subtasks.is_empty() {
+            return None;
+        }
+        let done = self.subtasks.iter().filter(|t| t.done).count();
+        Some((done, self.subtasks.len()))
+    }
+
+    /// Toggles the `n`th (1-based) subtask, and completes the todo when
+    /// `auto_complete` is set and nothing is left. A todo completed that way
+    /// is undone again once a subtask gets unchecked.
+    pub fn check(&mut self, n: usize, auto_complete: bool) -> crate::error::Result<()> {
+        let total = self.subtasks.len();
+        let task = n
+            .checked_sub(1)
+            .and_then(|i| self.subtasks.get_mut(i))
+            .ok_or_else(|| format!("no subtask {}, the item has {}", n, total))?;
+        task.done = !task.done;
+        let all_done = self.subtasks.iter().all(|t| t.done);
+        if auto_complete && self.ty.status() == UNDONE && all_done {
+            self.ty = Type::new(DONE);
+            self.auto_completed = true;
+        } else if self.auto_completed && self.ty.status() == DONE && !all_done {
+            self.ty = Type::new(UNDONE);
+            self.auto_completed = false;
+        }
+        Ok(())
+    }
+
+    pub fn parent(&self) -> Option<&String> {
+        self.links
+            .iter()
+            .find(|l| l.rel == Relation::Parent)
+            .map(|l| &l.id)
+    }
+
+    /// Links to `id`, an item has at most one parent.
+    pub fn link(&mut self, rel: Relation, id: String) {
+        if rel == Relation::Parent {
+            self.links.retain(|l| l.rel != Relation::Parent);
+        }
+        let link = Link { rel, id };
+        if !self.links.contains(&link) {
+            self.links.push(link);
+        }
+    }
+
+    /// Drops every link to `id`, returns whether there was any.
+    pub fn unlink(&mut self, id: &str) -> bool {
+        let len = self.links.len();
+        self.links.retain(|l| l.id != id);
+        len != self.links.len()
+    }
+
+    /// Finds an attachment by its 1-based index or its name.
+    pub fn attachment(&self, key: &str) -> Option<usize> {
+        match key.parse::<usize>() {
+            Ok(n) if n >= 1 && n <= self.attachments.len() => Some(n - 1),
+            _ => self.attachments.iter().position(|a| a.name == key),
+        }
+    }
+
+    pub fn checklist(&self) -> Option<String> {
+        if self.subtasks.is_empty() {
+            return None;
+        }
+        let tasks: Vec<_> = self.subtasks.iter().map(|t| t.to_string()).collect();
+        Some(tasks.join("; "))
+    }
+
+    /// Parses `[x] a; [ ] b` (or one subtask per line) into subtasks.
+    pub fn set_checklist(&mut self, checklist: Option<String>) {
+        self.subtasks = checklist
+            .iter()
+            .flat_map(|s| s.split([';', '\n']))
+            .map(str::trim)
+            .filter(|s| !s.is_empty())
+            .map(|s| {
+                let lower = s.to_lowercase();
+                if lower.starts_with("[x]") {
+                    Subtask::new(s[3..].trim(), true)
+                } else if lower.starts_with("[ ]") {
+                    Subtask::new(s[3..].trim(), false)
+                } else {
+                    Subtask::new(s, false)
+                }
+            })
+            .collect();
+    }
+
+    pub fn label_list(&self) -> Option<String> {
+        if self.labels.is_empty() {
+            None
+        } else {
+            Some(self.labels.join(", "))
+        }
+    }
+
+    /// Parses `a, b` into labels, dropping blanks and duplicates.
+    pub fn set_labels(&mut self, labels: Option<String>) {
+        self.labels.clear();
+        for label in labels.iter().flat_map(|s| s.split(',')).map(str::trim) {
+            if !label.is_empty() && !self.labels.iter().any(|l| l == label) {
+                self.labels.push(label.to_owned());
+            }
+        }
+    }
+}
+
+impl Display for Priority {
+    fn fmt(&self, f: &mut Formatter<'_>) -> Result {
+        let s = match self {
+            Priority::High => PRIORITIES[0],
+            Priority::Medium => PRIORITIES[1],
+            Priority::Low => PRIORITIES[2],
+        };
+        f.pad(s)
+    }
+}
+
+impl FromStr for Priority {
+    type Err = String;
+    fn from_str(s: &str) -> std::result::Result<Priority, String> {
+        Ok(match s {
+            "high" => Priority::High,
+            "medium" => Priority::Medium,
+            "low" => Priority::Low,
+            _ => {
+                return Err(format!(
+                    "unknown priority `{}`, expect one of {}",
+                    s,
+                    PRIORITIES.join(", ")
+                ))
+            }
+        })
+    }
+}
+
+impl Subtask {
+    pub fn new(summary: impl Into<String>, done: bool) -> Subtask {
+        Subtask {
+            summary: summary.into(),
+            done,
+        }
+    }
+}
+
+impl Display for Subtask {
+    fn fmt(&self, f: &mut Formatter<'_>) -> Result {
+        let mark = if self.done { 'x' } else { ' ' };
+        write!(f, "[{}] {}", mark, self.summary)
+    }
 }
 
 impl Display for Item {
     fn fmt(&self, f: &mut Formatter<'_>) -> Result {
-        write!(f, " {}  {}", self.ty.status(), self.summary)
+        f.write_str(&self.line(Icons::default()))
     }
 }
 
 impl Searchable for Item {
-    fn is_match(&self, pat: &String) -> bool {
+    fn is_match(&self, pat: &str) -> bool {
         self.ty.is_match(pat)
             || self.summary.contains(pat)
+            || self.labels.iter().any(|l| l.contains(pat))
             || self
                 .detail
                 .as_ref()
                 .map(|x| x.contains(pat))
                 .unwrap_or(false)
     }
+    fn flaggable(&self) -> bool {
+        true
+    }
+    fn line(&self, icons: Icons) -> String {
+        let mut line = format!(" {}  {}", icons.icon(self.ty.status()), self.summary);
+        if self.pinned {
+            line += &format!(" {}", icons.icon(PINNED));
+        }
+        if self.archived {
+            line += " (archived)";
+        }
+        if let Some(priority) = self.priority {
+            line += &format!(" ({} priority)", priority);
+        }
+        if let Some((done, total)) = self.progress() {
+            line += &format!(" [{}/{}]", done, total);
+        }
+        match self.due {
+            Some(due) if self.ty.status() == UNDONE => line + &format!(" (due {})", due),
+            _ => line,
+        }
+    }
+    fn clip(&self, part: Clip) -> Option<String> {
+        match part {
+            Clip::Summary => Some(self.summary.clone()),
+            Clip::Detail => self.detail.clone(),
+            Clip::Url if self.urls.is_empty() => None,
+            Clip::Url => {
+                let urls: Vec<_> = self.urls.iter().map(|r| r.url.as_str()).collect();
+                Some(urls.join("\n"))
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    #[test]
+    fn test_basis() {
+        let mut item = Item::new(Type::new(UNDONE), "release".to_owned());
+        item.set_checklist(Some("[x] tag; build\n[ ] publish".to_owned()));
+        assert_eq!(item.progress(), Some((1, 3)));
+        assert_eq!(item.checklist().unwrap(), "[x] tag; [ ] build; [ ] publish");
+        item.check(2, true).unwrap();
+        item.check(3, true).unwrap();
+        assert_eq!(item.ty.status(), DONE);
+        assert!(item.check(4, true).is_err());
+        item.check(1, true).unwrap();
+        assert_eq!(item.ty.status(), UNDONE);
+        let mut manual = Item::new(Type::new(DONE), "ship".to_owned());
+        manual.set_checklist(Some("[x] build".to_owned()));
+        manual.check(1, true).unwrap();
+        assert_eq!(manual.ty.status(), DONE);
+        item.set_labels(Some("work, urgent,,work".to_owned()));
+        assert_eq!(item.labels, vec!["work", "urgent"]);
+        assert!(item.is_match("urg"));
+
+        let prev = Item::new(Type::new(UNDONE), "backup".to_owned());
+        let mut done = prev.clone();
+        done.recur = "weekly".parse().ok();
+        done.ty = Type::new(DONE);
+        let next = done.next_occurrence(&prev).unwrap();
+        assert_eq!(next.ty.status(), UNDONE);
+        assert!(next.recur.is_some());
+        assert!(done.recur.is_none());
+        assert!(done.next_occurrence(&prev).is_none());
+    }
 }
@@ -0,0 +1,165 @@
+use crate::{clipboard::Clip, interaction::Searchable, status::Icons, store::ItemPair};
+use serde::{Deserialize, Serialize};
+use std::{
+    collections::HashSet,
+    fmt::{Display, Formatter, Result},
+    str::FromStr,
+};
+
+pub const RALL: &[&str; 4] = &["parent", "blocks", "related", "derived-from"];
+
+/// How the item holding a link relates to the link target.
+#[derive(Serialize, Deserialize, Clone, Copy, PartialEq, Debug)]
+#[serde(rename_all = "kebab-case")]
+pub enum Relation {
+    Parent,
+    Blocks,
+    Related,
+    DerivedFrom,
+}
+
+#[derive(Serialize, Deserialize, Clone, PartialEq, Debug)]
+pub struct Link {
+    pub rel: Relation,
+    pub id: String,
+}
+
+impl Relation {
+    /// The relation read from the target's side.
+    pub fn inverse(self) -> &'static str {
+        match self {
+            Relation::Parent => "child",
+            Relation::Blocks => "blocked by",
+            Relation::Related => "related",
+            Relation::DerivedFrom => "source of",
+        }
+    }
+}
+
+impl Display for Relation {
+    fn fmt(&self, f: &mut Formatter<'_>) -> Result {
+        let s = match self {
+            Relation::Parent => RALL[0],
+            Relation::Blocks => RALL[1],
+            Relation::Related => RALL[2],
+            Relation::DerivedFrom => RALL[3],
+        };
+        f.pad(s)
+    }
+}
+
+impl FromStr for Relation {
+    type Err = String;
+    fn from_str(s: &str) -> std::result::Result<Relation, String> {
+        Ok(match s {
+            "parent" => Relation::Parent,
+            "blocks" => Relation::Blocks,
+            "related" => Relation::Related,
+            "derived-from" => Relation::DerivedFrom,
+            _ => return Err(format!("unknown relation `{}`", s)),
+        })
+    }
+}
+
+/// An item placed in the parent/child hierarchy.
+#[derive(Clone)]
+pub struct Node {
+    pub depth: usize,
+    pub pair: ItemPair,
+}
+
+impl Display for Node {
+    fn fmt(&self, f: &mut Formatter<'_>) -> Result {
+        f.write_str(&self.line(Icons::default()))
+    }
+}
+
+impl Searchable for Node {
+    fn is_match(&self, pat: &str) -> bool {
+        self.pair.is_match(pat)
+    }
+    fn flaggable(&self) -> bool {
+        true
+    }
+    fn line(&self, icons: Icons) -> String {
+        match self.depth {
+            0 => self.pair.line(icons),
+            depth => format!("{}└{}", "  ".repeat(depth - 1), self.pair.line(icons)),
+        }
+    }
+    fn clip(&self, part: Clip) -> Option<String> {
+        self.pair.clip(part)
+    }
+}
+
+/// Reorders `items` so children directly follow their parent, keeping the
+/// original order among siblings.
+pub fn tree(items: Vec<ItemPair>) -> Vec<Node> {
+    let ids: HashSet<_> = items.iter().map(|x| x.id.clone()).collect();
+    let is_root = |x: &ItemPair| match x.item.parent() {
+        Some(parent) => !ids.contains(parent) || parent == &x.id,
+        None => true,
+    };
+    let mut nodes = Vec::with_capacity(items.len());
+    let mut visited = HashSet::new();
+    for root in items.iter().filter(|x| is_root(x)) {
+        push_node(&items, root, 0, &mut visited, &mut nodes);
+    }
+    // items caught in a parent cycle have no root, keep them visible anyway
+    for x in &items {
+        if !visited.contains(&x.id) {
+            push_node(&items, x, 0, &mut visited, &mut nodes);
+        }
+    }
+    nodes
+}
+
+fn push_node(
+    items: &[ItemPair],
+    pair: &ItemPair,
+    depth: usize,
+    visited: &mut HashSet<String>,
+    nodes: &mut Vec<Node>,
+) {
+    if !visited.insert(pair.id.clone()) {
+        return;
+    }
+    nodes.push(Node {
+        depth,
+        pair: pair.clone(),
+    });
+    for child in items.iter().filter(|x| x.item.parent() == Some(&pair.id)) {
+        push_node(items, child, depth + 1, visited, nodes);
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::{item::Item, status::*};
+    #[test]
+    fn test_basis() {
+        let pair = |id: &str, parent: Option<&str>| {
+            let mut item = Item::new(Type::new(IDEA), id.to_owned());
+            if let Some(parent) = parent {
+                item.link(Relation::Parent, parent.to_owned());
+            }
+            ItemPair {
+                id: id.to_owned(),
+                item,
+            }
+        };
+        let nodes = tree(vec![
+            pair("3", Some("1")),
+            pair("2", None),
+            pair("1", None),
+            pair("4", Some("3")),
+        ]);
+        let order: Vec<_> = nodes
+            .iter()
+            .map(|n| (n.pair.id.as_str(), n.depth))
+            .collect();
+        assert_eq!(order, vec![("2", 0), ("1", 0), ("3", 1), ("4", 2)]);
+        assert_eq!("derived-from".parse(), Ok(Relation::DerivedFrom));
+    }
+}
@@ -1,24 +1,51 @@
+mod attachment;
+mod clipboard;
+mod config;
+mod date;
+mod digest;
 mod error;
+mod filter;
 mod interaction;
 mod item;
+mod link;
+mod query;
+mod recur;
+mod reference;
+mod stats;
 mod status;
 mod store;
 
-use clap::{clap_app, crate_authors, crate_description, crate_version};
-use crossterm::style::Colorize;
-use error::Result;
-use interaction::{Interaction, Is, Op, IC};
-use status::TALL;
-use std::io::{stdin, stdout};
+use clap::{clap_app, crate_authors, crate_description, crate_version, Arg};
+use clipboard::Clipboard;
+use config::Config;
+use crossterm::style::{self, Colorize};
+use date::Date;
+use error::{Error, Result};
+use filter::{Filter, SORTS, STATUSES};
+use interaction::{color_enabled, plain_terminal, Script, Searchable, Theme};
+use interaction::{BulkOp, Interaction, Is, KeyMap, Op, PickState, TuiOp, ViewOp, IC, II};
+use item::{Flag, Item, PRIORITIES};
+use link::{tree, Node, Relation, RALL};
+use reference::Reference;
+use stats::Stats;
+use status::{Icons, Type, ICONS, TALL, TIDEA};
+use std::collections::BTreeSet;
+use std::io::{self, stdin, stdout, Read, Write};
 use store::{ItemPair, Store};
 
-const DATA_DIR: &'static str = ".config/ideas";
+const DATA_DIR: &str = ".config/ideas";
 
 fn main() {
     match main_throw_err() {
         Ok(_) => {}
+        // the reader went away early, as in `ideas list | head -1`
+        Err(Error::Io(e)) if e.kind() == io::ErrorKind::BrokenPipe => {}
         Err(e) => {
-            let head = "error:".red();
+            let head = if color_enabled() {
+                "error:".red()
+            } else {
+                style::style("error:")
+            };
             eprintln!("{} {}", head, e);
         }
     }
@@ -29,10 +56,112 @@ fn main_throw_err() -> Result<()> {
         (version: crate_version!())
         (author: crate_authors!())
         (about: crate_description!())
-        (@arg SHORT: --short "write idea quickly, skip detail and related url")
+        (@arg SHORT: --short +global "write idea quickly, skip detail and related url")
+        (@arg SUMMARY: [SUMMARY]... "capture an idea with this summary, without any prompt; use `ideas add` when it starts with a command name")
+        (@arg STDIN: --stdin requires[SUMMARY] "read the detail of the captured idea from stdin, waiting until it ends")
+        (@arg NO_TTY: --("no-tty") "numbered menus and plain prompts, for terminals that can't be drawn on")
+        (@arg KEYS: --keys [KEYS] conflicts_with[NO_TTY] "press these keys instead of reading the terminal, e.g. 'buy milk<enter>', with keys named as in `ideas config keys`")
         (@subcommand view =>
             (about: "list ideas")
-            (@arg TAG: -t --tag [TAG]... possible_values(TALL) "filter ideas by tag"))
+            (@arg QUERY: "saved query to run, as `@name`")
+            (args: filter_args())
+            (@arg MULTI: -m --multi "select several ideas and operate on all of them")
+            (@arg SESSION: -s --session "return to the list after each operation, until Cancel or Esc"))
+        (@subcommand bulk =>
+            (about: "operate on many ideas at once")
+            (@arg OP: +required possible_values(&["done", "undone", "retag", "delete"]) "operation to apply")
+            (@arg IDS: --ids [IDS] "comma separated ids of the ideas, archived or not")
+            (args: filter_args())
+            (mut_arg: "MATCH", |arg| arg.visible_alias("filter"))
+            (@arg TO: --to [TAG] possible_values(TALL) "new tag for `retag`")
+            (@arg YES: -y --yes "delete without confirmation"))
+        (@subcommand add =>
+            (about: "capture an idea without any prompt, reading its detail from stdin with `--stdin`")
+            (@arg TAG: -t --tag [TAG] possible_values(TALL) default_value("idea") "tag of the idea")
+            (@arg FROM_CLIPBOARD: --("from-clipboard") "take the url, or else the detail, from the clipboard")
+            (@arg STDIN: --stdin "read the detail from stdin, waiting until it ends")
+            (@arg PRIORITY: -p --priority [LEVEL] possible_values(PRIORITIES) "priority of the idea")
+            (@arg SUMMARY: [SUMMARY]... "summary of the idea, the first line of the clipboard by default"))
+        (@subcommand tui =>
+            (about: "browse and triage ideas in a full-screen interface"))
+        (@subcommand list =>
+            (about: "print ideas with their ids")
+            (@arg QUERY: "saved query to run, as `@name`")
+            (args: filter_args()))
+        (@subcommand stats =>
+            (about: "report counts, completion and the most used labels")
+            (@arg WEEKS: --weeks [N] default_value("8") "weeks to report per week")
+            (@arg JSON: --json "print as JSON"))
+        (@subcommand digest =>
+            (about: "report what was captured, updated and completed lately, in Markdown")
+            (@arg SINCE: --since [WINDOW] default_value("7d") "start of the window: 7d, 2w, 12h or a date"))
+        (@subcommand pin =>
+            (about: "keep an idea at the top of listings")
+            (@arg ID: +required "id of the idea"))
+        (@subcommand unpin =>
+            (about: "let an idea go back to its place in listings")
+            (@arg ID: +required "id of the idea"))
+        (@subcommand archive =>
+            (about: "hide an idea from listings unless `--archived`")
+            (@arg ID: +required "id of the idea"))
+        (@subcommand unarchive =>
+            (about: "show an archived idea in listings again")
+            (@arg ID: +required "id of the idea"))
+        (@subcommand priority =>
+            (about: "set how urgent an idea is, for `--sort priority`")
+            (@arg ID: +required "id of the idea")
+            (@arg LEVEL: +required possible_values(&["high", "medium", "low", "none"]) "priority of the idea, `none` clears it"))
+        (@subcommand check =>
+            (about: "toggle a subtask of an idea")
+            (@arg ID: +required "id of the idea")
+            (@arg N: +required "index of the subtask, starting from 1"))
+        (@subcommand link =>
+            (about: "link an idea to another")
+            (@arg A: +required "id of the idea to link from")
+            (@arg B: +required "id of the idea to link to, `--as parent` makes it the parent of A")
+            (@arg AS: --as [RELATION] possible_values(RALL) default_value("related") "relation of A to B"))
+        (@subcommand unlink =>
+            (about: "remove links between two ideas")
+            (@arg A: +required "id of the idea to unlink from")
+            (@arg B: +required "id of the linked idea"))
+        (@subcommand open =>
+            (about: "open a reference of an idea with the system opener")
+            (@arg ID: +required "id of the idea")
+            (@arg N: "index of the reference, starting from 1"))
+        (@subcommand attach =>
+            (about: "attach a file to an idea")
+            (@arg ID: +required "id of the idea")
+            (@arg FILE: +required "file to attach"))
+        (@subcommand attachment =>
+            (about: "manage attachments")
+            (@setting SubcommandRequiredElseHelp)
+            (@subcommand get =>
+                (about: "copy an attachment out")
+                (@arg ID: +required "id of the idea")
+                (@arg NAME: +required "index or name of the attachment")
+                (@arg DEST: "destination, the current directory by default"))
+            (@subcommand rm =>
+                (about: "remove an attachment")
+                (@arg ID: +required "id of the idea")
+                (@arg NAME: +required "index or name of the attachment"))
+            (@subcommand gc =>
+                (about: "delete stored files no idea refers to")))
+        (@subcommand query =>
+            (about: "manage saved queries, run with `ideas view @name`")
+            (@setting SubcommandRequiredElseHelp)
+            (@subcommand save =>
+                (about: "save the filter flags under a name")
+                (@arg NAME: +required "name of the query")
+                (args: filter_args()))
+            (@subcommand list =>
+                (about: "print the saved queries"))
+            (@subcommand rm =>
+                (about: "delete a saved query")
+                (@arg NAME: +required "name of the query")))
+        (@subcommand config =>
+            (about: "get or set configurations")
+            (@arg KEY: "configuration key")
+            (@arg VALUE: "new value of the key"))
     )
     .get_matches();
 
@@ -43,39 +172,354 @@ fn main_throw_err() -> Result<()> {
         })
         .unwrap();
     let store = Store::open(&dir)
-        .expect(format!("Load db fault! Try again after exec `rm -rf {:#?}`", &dir).as_str());
+        .unwrap_or_else(|_| panic!("Load db fault! Try again after exec `rm -rf {:#?}`", &dir));
+
+    let config = store.config()?;
+    let clipboard = Clipboard::new(&config.copy_command, &config.paste_command);
+    if let Some(item) = quick_item(&matches, &clipboard)? {
+        return store.insert(&item);
+    }
 
     let input = stdin();
     let output = stdout();
-    let mut interaction = Interaction::new(IC::new(input.lock(), output.lock()));
+    let mut core = IC::new(input.lock(), output.lock());
+    core.keymap(KeyMap::from_config(&config.keymap, &config.keys)?)
+        .history(store.history()?)
+        .plain(matches.is_present("NO_TTY") || (!matches.is_present("KEYS") && plain_terminal()));
+    let mut interaction = Interaction::new(core);
+    if let Some(keys) = matches.value_of("KEYS") {
+        interaction.events(Box::new(Script::parse(keys)?));
+    }
+    let icons = Icons::named(&config.icons).unwrap_or_default();
+    let mut theme = Theme::named(&config.theme, color_enabled())?;
+    theme.icons = icons;
+    interaction
+        .mouse(config.mouse)
+        .clipboard(clipboard)
+        .theme(theme);
+    let known = store.clone();
+    interaction.completions(move || known_values(&known));
 
-    match matches.subcommand() {
+    let result = match matches.subcommand() {
+        Some(("view", submatches)) if submatches.is_present("MULTI") => {
+            let filter = view_filter(&store, &mut interaction, submatches)?;
+            let items = tree(filtered_items(&store, &filter)?);
+            let pairs: Vec<_> = interaction
+                .view_items_multi(items)?
+                .into_iter()
+                .map(|node| node.pair)
+                .collect();
+            let op = interaction.bulk_op()?;
+            if let BulkOp::Cancel = op {
+                println!("cancelled");
+                return Ok(());
+            }
+            let to = match op {
+                BulkOp::Retag => Some(interaction.select_type()?),
+                BulkOp::Delete => match interaction.confirm_again()? {
+                    Is::Yes => None,
+                    Is::No => return Ok(()),
+                },
+                _ => None,
+            };
+            let n = apply_bulk(&store, pairs, &op, to.as_ref())?;
+            println!("{} idea(s) changed", n);
+            Ok(())
+        }
+        Some(("bulk", submatches)) => {
+            let filter = Filter::from_matches(submatches)?;
+            if !submatches.is_present("IDS") && !filter.narrows() {
+                return Err(Error::StringError(
+                    "select ideas with `--ids`, `--match` or another filter flag".to_owned(),
+                ));
+            }
+            let ids: Option<Vec<_>> = submatches
+                .value_of("IDS")
+                .map(|ids| ids.split(',').map(str::trim).collect());
+            let mut missing = vec![];
+            for &id in ids.iter().flatten() {
+                if store.get(id.to_owned())?.is_none() {
+                    missing.push(id);
+                }
+            }
+            if !missing.is_empty() {
+                return Err(Error::StringError(format!(
+                    "no idea with id `{}`",
+                    missing.join("`, `")
+                )));
+            }
+            let pairs = match &ids {
+                Some(ids) => filter.apply_ids(all_items(&store)?, ids)?,
+                None => filter.apply(all_items(&store)?)?,
+            };
+            let op = match submatches.value_of("OP").unwrap() {
+                "done" => BulkOp::Done,
+                "undone" => BulkOp::Undone,
+                "retag" => BulkOp::Retag,
+                _ => BulkOp::Delete,
+            };
+            let to = submatches.value_of("TO").and_then(Type::from_desc);
+            if let BulkOp::Retag = op {
+                if to.is_none() {
+                    return Err(Error::StringError("`retag` needs `--to`".to_owned()));
+                }
+            }
+            if let BulkOp::Delete = op {
+                if !submatches.is_present("YES") {
+                    for pair in &pairs {
+                        println!("{:>6} {}", pair.id, pair.item.line(icons));
+                    }
+                    if let Is::No = interaction.confirm_again()? {
+                        return Ok(());
+                    }
+                }
+            }
+            let n = apply_bulk(&store, pairs, &op, to.as_ref())?;
+            println!("{} idea(s) changed", n);
+            Ok(())
+        }
+        Some(("view", submatches)) if submatches.is_present("SESSION") => {
+            let filter = view_filter(&store, &mut interaction, submatches)?;
+            let mut state = PickState::default();
+            loop {
+                let items = tree(filtered_items(&store, &filter)?);
+                let pair = match interaction.view_items_resume(items, &mut state)? {
+                    Some(node) => node.pair,
+                    None => break,
+                };
+                if let Some(flag) = state.flag {
+                    toggle_flag(&store, pair, flag)?;
+                    continue;
+                }
+                match interaction.curd(&pair.item)? {
+                    Op::Cancel => break,
+                    op => operate(&store, &mut interaction, op, pair, icons)?,
+                }
+            }
+            Ok(())
+        }
         Some(("view", submatches)) => {
-            let tags;
-            if let Some(iter) = submatches.values_of("TAG") {
-                tags = iter.collect::<Vec<_>>();
+            let filter = view_filter(&store, &mut interaction, submatches)?;
+            let mut state = PickState::default();
+            loop {
+                let items = tree(filtered_items(&store, &filter)?);
+                let Node { pair, .. } = interaction.view_items(items, &mut state)?;
+                match state.flag {
+                    Some(flag) => toggle_flag(&store, pair, flag)?,
+                    None => {
+                        let op = interaction.curd(&pair.item)?;
+                        break operate(&store, &mut interaction, op, pair, icons);
+                    }
+                };
+            }
+        }
+        Some(("tui", _)) => {
+            let mut tui = interaction.tui();
+            loop {
+                let items = store.iter().collect::<Result<Vec<_>>>()?;
+                let op = interaction.run_tui(&mut tui, items)?;
+                let done = match op {
+                    TuiOp::Quit => break,
+                    TuiOp::Add => interaction
+                        .fill_item(false)
+                        .and_then(|item| store.insert(&item))
+                        .map(|_| "added".to_owned()),
+                    TuiOp::View(pair) => view_idea(&store, &mut interaction, pair, icons),
+                    TuiOp::Edit(ItemPair { id, item }) => interaction
+                        .update_item(item)
+                        .and_then(|item| store.update(id.clone(), &item))
+                        .map(|_| format!("#{} updated", id)),
+                    TuiOp::Toggle(ItemPair { id, mut item }) => {
+                        toggle_status(&store, &id, &mut item, icons)
+                    }
+                    TuiOp::Flag(pair, flag) => toggle_flag(&store, pair, flag),
+                    TuiOp::Delete(ItemPair { id, .. }) => {
+                        interaction.confirm_again().and_then(|is| match is {
+                            Is::Yes => store.remove(id.clone()).map(|_| format!("#{} deleted", id)),
+                            Is::No => Ok("nothing deleted".to_owned()),
+                        })
+                    }
+                };
+                match done {
+                    Ok(message) => tui.message(message),
+                    Err(e) => tui.message(format!("error: {}", e)),
+                }
+            }
+            Ok(())
+        }
+        Some(("list", submatches)) => {
+            let filter = listing_filter(&store, submatches)?;
+            let mut out = output.lock();
+            for node in tree(filtered_items(&store, &filter)?) {
+                writeln!(out, "{:>6} {}", node.pair.id, node.line(icons))?;
+            }
+            Ok(())
+        }
+        Some(("stats", submatches)) => {
+            let weeks = submatches
+                .value_of("WEEKS")
+                .unwrap()
+                .parse()
+                .map_err(|_| Error::StringError("`--weeks` expects a number".to_owned()))?;
+            let items = store
+                .iter()
+                .map(|pair| pair.map(|pair| pair.item))
+                .collect::<Result<Vec<_>>>()?;
+            let stats = Stats::collect(&items, Date::today(), weeks);
+            if submatches.is_present("JSON") {
+                println!("{}", serde_json::to_string_pretty(&stats)?);
             } else {
-                tags = TALL.to_vec();
+                interaction.print_markdown(&stats.markdown(icons));
             }
-            let mut items: Vec<_> = store
+            Ok(())
+        }
+        Some(("digest", submatches)) => {
+            let start = date::parse_since(
+                submatches.value_of("SINCE").unwrap(),
+                std::time::SystemTime::now(),
+            )?;
+            let items = store
                 .iter()
-                .map(|x| x.unwrap())
-                .filter(|x| tags.contains(&x.item.ty.desc().as_str()))
-                .collect();
-            items.sort_unstable_by(|a, b| b.item.time.partial_cmp(&a.item.time).unwrap());
-            let ItemPair { id, mut item } = interaction.view_items(items)?;
-            let op = interaction.curd()?;
-            match op {
-                Op::Retrieve => interaction.view_item(item)?,
-                Op::Delete => match interaction.confirm_again()? {
-                    Is::Yes => store.remove(id)?,
-                    _ => {}
-                },
-                Op::Update => {
-                    item = interaction.update_item(item)?;
-                    store.update(id, &item)?
+                .map(|pair| pair.map(|pair| pair.item))
+                .collect::<Result<Vec<_>>>()?;
+            interaction.print_markdown(&digest::digest(&items, start));
+            Ok(())
+        }
+        Some((name @ ("pin" | "unpin" | "archive" | "unarchive"), submatches)) => {
+            let flag = match name.ends_with("pin") {
+                true => Flag::Pinned,
+                false => Flag::Archived,
+            };
+            let id = submatches.value_of("ID").unwrap().to_owned();
+            let mut item = get_item(&store, &id)?;
+            item.set_flag(flag, !name.starts_with("un"));
+            store.update(id, &item)?;
+            println!("{}", item.line(icons));
+            Ok(())
+        }
+        Some(("priority", submatches)) => {
+            let id = submatches.value_of("ID").unwrap().to_owned();
+            let mut item = get_item(&store, &id)?;
+            item.priority = match submatches.value_of("LEVEL").unwrap() {
+                "none" => None,
+                level => Some(level.parse()?),
+            };
+            store.update(id, &item)?;
+            println!("{}", item.line(icons));
+            Ok(())
+        }
+        Some(("check", submatches)) => {
+            let id = submatches.value_of("ID").unwrap().to_owned();
+            let n = parse_index(submatches.value_of("N").unwrap())?;
+            let mut item = get_item(&store, &id)?;
+            item.check(n, store.config()?.auto_complete)?;
+            store.update(id, &item)?;
+            println!("{}", item.line(icons));
+            Ok(())
+        }
+        Some(("link", submatches)) => {
+            let a = submatches.value_of("A").unwrap().to_owned();
+            let b = submatches.value_of("B").unwrap().to_owned();
+            let rel: Relation = submatches.value_of("AS").unwrap().parse()?;
+            store.link(a, b, rel)
+        }
+        Some(("unlink", submatches)) => {
+            let a = submatches.value_of("A").unwrap().to_owned();
+            let b = submatches.value_of("B").unwrap();
+            let mut item = get_item(&store, &a)?;
+            if !item.unlink(b) {
+                return Err(Error::StringError(format!(
+                    "`{}` is not linked to `{}`",
+                    a, b
+                )));
+            }
+            store.update(a, &item)
+        }
+        Some(("open", submatches)) => {
+            let id = submatches.value_of("ID").unwrap();
+            let n = parse_index(submatches.value_of("N").unwrap_or("1"))?;
+            let item = get_item(&store, id)?;
+            let len = item.urls.len();
+            let reference = n
+                .checked_sub(1)
+                .and_then(|i| item.urls.get(i))
+                .ok_or_else(|| {
+                    Error::StringError(format!("no reference {}, the idea has {}", n, len))
+                })?;
+            reference.open()
+        }
+        Some(("attach", submatches)) => {
+            let id = submatches.value_of("ID").unwrap().to_owned();
+            let mut item = get_item(&store, &id)?;
+            let attachment = store.blobs().put(submatches.value_of("FILE").unwrap())?;
+            if !item.attachments.contains(&attachment) {
+                item.attachments.push(attachment);
+            }
+            store.update(id, &item)
+        }
+        Some(("attachment", submatches)) => match submatches.subcommand() {
+            Some(("get", m)) => {
+                let item = get_item(&store, m.value_of("ID").unwrap())?;
+                let attachment = &item.attachments[get_attachment(&item, m)?];
+                let dest = store
+                    .blobs()
+                    .get(attachment, m.value_of("DEST").unwrap_or("."))?;
+                println!("{}", dest.display());
+                Ok(())
+            }
+            Some(("rm", m)) => {
+                let id = m.value_of("ID").unwrap().to_owned();
+                let mut item = get_item(&store, &id)?;
+                let n = get_attachment(&item, m)?;
+                item.attachments.remove(n);
+                store.update(id, &item)?;
+                store.gc()?;
+                Ok(())
+            }
+            Some(("gc", _)) => {
+                println!("{} file(s) removed", store.gc()?);
+                Ok(())
+            }
+            _ => unreachable!(),
+        },
+        Some(("query", submatches)) => match submatches.subcommand() {
+            Some(("save", m)) => {
+                let name = query_name(m.value_of("NAME").unwrap())?;
+                let filter = Filter::from_matches(m)?;
+                store.save_query(name, &filter)?;
+                println!("@{}  {}", name, filter);
+                Ok(())
+            }
+            Some(("list", _)) => {
+                let mut out = output.lock();
+                for (name, filter) in store.queries()? {
+                    writeln!(out, "@{}  {}", name, filter)?;
+                }
+                Ok(())
+            }
+            Some(("rm", m)) => {
+                let name = query_name(m.value_of("NAME").unwrap())?;
+                if !store.remove_query(name)? {
+                    return Err(no_query(name));
+                }
+                Ok(())
+            }
+            _ => unreachable!(),
+        },
+        Some(("config", submatches)) => {
+            let mut config = store.config()?;
+            match (submatches.value_of("KEY"), submatches.value_of("VALUE")) {
+                (Some(key), Some(value)) => {
+                    config.set(key, value)?;
+                    check_config(&config)?;
+                    store.set_config(&config)?;
+                }
+                (Some(key), None) => println!("{}", config.get(key)?),
+                _ => {
+                    for (key, value) in config.entries()? {
+                        println!("{} = {}", key, value);
+                    }
                 }
-                _ => {}
             }
             Ok(())
         }
@@ -85,5 +529,296 @@ fn main_throw_err() -> Result<()> {
             store.insert(&item)?;
             Ok(())
         }
+    };
+    // a history that fails to save shouldn't hide how the command went
+    let saved = match interaction.take_history() {
+        Some(history) => store.set_history(&history),
+        None => Ok(()),
+    };
+    result.and(saved)
+}
+
+/// The flags `view`, `list`, `bulk` and `query save` narrow ideas with, read
+/// back by `Filter::from_matches`.
+fn filter_args() -> Vec<Arg<'static>> {
+    vec![
+        clap_app!(@arg (Arg::new("TAG")) (-) -t --tag [TAG]... possible_values(TALL) "filter ideas by tag"),
+        clap_app!(@arg (Arg::new("STATUS")) (-) --status [STATUS] possible_values(STATUSES) "only todos done or undone"),
+        clap_app!(@arg (Arg::new("SINCE")) (-) --since [WHEN] "only ideas changed since: 7d, 2w, 12h or a date"),
+        clap_app!(@arg (Arg::new("UNTIL")) (-) --until [WHEN] "only ideas changed until: 7d, 2w, 12h or a date"),
+        clap_app!(@arg (Arg::new("HAS_URL")) (-) --("has-url") "only ideas with a related url"),
+        clap_app!(@arg (Arg::new("HAS_DETAIL")) (-) --("has-detail") "only ideas with a detail"),
+        clap_app!(@arg (Arg::new("MATCH")) (-) --match [TEXT] "only ideas matching the text"),
+        clap_app!(@arg (Arg::new("WHERE")) (-) --where [EXPR] "only ideas matching the expression, e.g. 'type:todo and label~work'"),
+        clap_app!(@arg (Arg::new("LIMIT")) (-) --limit [N] "show at most N ideas"),
+        clap_app!(@arg (Arg::new("SORT")) (-) --sort [SORT] possible_values(SORTS) "order of the ideas, newest first by default"),
+        clap_app!(@arg (Arg::new("REVERSE")) (-) --reverse "reverse the order"),
+        clap_app!(@arg (Arg::new("ARCHIVED")) (-) --archived "include archived ideas"),
+    ]
+}
+
+/// Rejects settings that would only fail on the next run.
+fn check_config(config: &Config) -> Result<()> {
+    KeyMap::from_config(&config.keymap, &config.keys)?;
+    Theme::named(&config.theme, true)?;
+    if Icons::named(&config.icons).is_none() {
+        return Err(Error::StringError(format!(
+            "unknown icons `{}`, expect one of {}",
+            config.icons,
+            ICONS.join(", ")
+        )));
+    }
+    Ok(())
+}
+
+/// Every label and url in use, for completion.
+fn known_values(store: &Store) -> Result<(Vec<String>, Vec<String>)> {
+    let mut labels = BTreeSet::new();
+    let mut urls = BTreeSet::new();
+    for pair in store.iter() {
+        let item = pair?.item;
+        labels.extend(item.labels);
+        urls.extend(item.urls.into_iter().map(|r| r.url));
+    }
+    Ok((labels.into_iter().collect(), urls.into_iter().collect()))
+}
+
+/// The item `ideas <summary>` or `ideas add` captures, `None` for other
+/// commands. Its detail is stdin with `--stdin`, unless `--short`, and
+/// `--from-clipboard` adds the clipboard as its url if it is one, or else
+/// to its detail. Stdin isn't read otherwise, as an inherited pipe may
+/// never end.
+fn quick_item(matches: &clap::ArgMatches, clipboard: &Clipboard) -> Result<Option<Item>> {
+    let (args, tag) = match matches.subcommand() {
+        Some(("add", submatches)) => (submatches, submatches.value_of("TAG").unwrap()),
+        None if matches.is_present("SUMMARY") => (matches, TIDEA),
+        _ => return Ok(None),
+    };
+    let clip = if args.is_present("FROM_CLIPBOARD") {
+        clipboard.paste()?.trim().to_owned()
+    } else {
+        String::new()
+    };
+    let reference = Some(&clip)
+        .filter(|clip| !clip.contains('\n'))
+        .and_then(|clip| clip.parse::<Reference>().ok());
+    let summary: Vec<_> = args.values_of("SUMMARY").into_iter().flatten().collect();
+    let mut summary = summary.join(" ").trim().to_owned();
+    if summary.is_empty() {
+        summary = clip.lines().next().unwrap_or("").trim().to_owned();
+    }
+    if summary.is_empty() {
+        return Err(Error::StringError(
+            "the field `idea` is required!".to_string(),
+        ));
+    }
+    let mut item = Item::new(Type::from_desc(tag).unwrap(), summary);
+    item.priority = args.value_of("PRIORITY").map(str::parse).transpose()?;
+    let mut detail = String::new();
+    if args.is_present("STDIN") && !matches.is_present("SHORT") {
+        stdin().read_to_string(&mut detail)?;
+    }
+    match reference {
+        Some(reference) => item.urls.push(reference),
+        None if clip != item.summary => detail = format!("{}\n\n{}", detail.trim(), clip),
+        None => {}
+    }
+    let detail = detail.trim();
+    if !detail.is_empty() {
+        item.detail = Some(detail.to_owned());
+    }
+    Ok(Some(item))
+}
+
+fn all_items(store: &Store) -> Result<Vec<ItemPair>> {
+    store.iter().collect()
+}
+
+fn filtered_items(store: &Store, filter: &Filter) -> Result<Vec<ItemPair>> {
+    filter.apply(all_items(store)?)
+}
+
+/// The filter of a listing: the saved query given as `@name`, or else
+/// the filter flags.
+fn listing_filter(store: &Store, matches: &clap::ArgMatches) -> Result<Filter> {
+    let filter = Filter::from_matches(matches)?;
+    let query = match matches.value_of("QUERY") {
+        Some(query) => query,
+        None => return Ok(filter),
+    };
+    let name = query
+        .strip_prefix('@')
+        .ok_or_else(|| Error::StringError(format!("saved queries are run as `@{}`", query)))?;
+    if filter != Filter::default() {
+        return Err(Error::StringError(
+            "a saved query takes no other filter flags".to_owned(),
+        ));
+    }
+    store.query(name)?.ok_or_else(|| no_query(name))
+}
+
+/// Like `listing_filter`, but a bare `ideas view` first offers the saved
+/// queries, if any.
+fn view_filter(store: &Store, interaction: &mut II, matches: &clap::ArgMatches) -> Result<Filter> {
+    let filter = listing_filter(store, matches)?;
+    if matches.is_present("QUERY") || filter != Filter::default() {
+        return Ok(filter);
+    }
+    let mut queries = store.queries()?;
+    if queries.is_empty() {
+        return Ok(filter);
+    }
+    Ok(match interaction.select_query(&queries)? {
+        Some(n) => queries.swap_remove(n).1,
+        None => filter,
+    })
+}
+
+/// Flips a todo between done and undone, telling which way it went.
+fn toggle_status(store: &Store, id: &str, item: &mut Item, icons: Icons) -> Result<String> {
+    match item.ty.toggle() {
+        Some(status) => {
+            item.ty = Type::new(status);
+            item.time = std::time::SystemTime::now();
+            store.update(id.to_owned(), item)?;
+            Ok(format!("#{} marked {}", id, icons.icon(status)))
+        }
+        None => Ok(format!("#{} is not a todo", id)),
+    }
+}
+
+/// Flips `flag` on the idea, telling which way it went.
+fn toggle_flag(store: &Store, pair: ItemPair, flag: Flag) -> Result<String> {
+    let ItemPair { id, mut item } = pair;
+    let on = !item.flag(flag);
+    item.set_flag(flag, on);
+    store.update(id.clone(), &item)?;
+    let not = if on { "" } else { "un" };
+    Ok(format!("#{} {}{}", id, not, flag.name()))
+}
+
+fn query_name(name: &str) -> Result<&str> {
+    let name = name.strip_prefix('@').unwrap_or(name);
+    if name.is_empty() || name.contains(char::is_whitespace) {
+        return Err(Error::StringError(format!(
+            "invalid query name `{}`, expect a single word",
+            name
+        )));
+    }
+    Ok(name)
+}
+
+fn no_query(name: &str) -> Error {
+    Error::StringError(format!(
+        "no saved query `@{}`, see `ideas query list`",
+        name
+    ))
+}
+
+fn get_item(store: &Store, id: &str) -> Result<Item> {
+    store
+        .get(id.to_owned())?
+        .ok_or_else(|| Error::StringError(format!("no idea with id `{}`", id)))
+}
+
+fn parse_index(n: &str) -> Result<usize> {
+    n.parse()
+        .map_err(|_| Error::StringError(format!("`{}` is not a valid index", n)))
+}
+
+fn get_attachment(item: &Item, matches: &clap::ArgMatches) -> Result<usize> {
+    let name = matches.value_of("NAME").unwrap();
+    item.attachment(name)
+        .ok_or_else(|| Error::StringError(format!("no attachment `{}`", name)))
+}
+
+fn apply_bulk(
+    store: &Store,
+    pairs: Vec<ItemPair>,
+    op: &BulkOp,
+    to: Option<&Type>,
+) -> Result<usize> {
+    let mut n = 0;
+    let mut removed = vec![];
+    for ItemPair { id, mut item } in pairs {
+        let status = match op {
+            BulkOp::Done => item.ty.next_status(),
+            BulkOp::Undone => item.ty.last_status(),
+            BulkOp::Retag => to
+                .filter(|ty| ty.desc() != item.ty.desc())
+                .map(|ty| ty.status()),
+            BulkOp::Delete => {
+                removed.push(id);
+                continue;
+            }
+            BulkOp::Cancel => None,
+        };
+        if let Some(status) = status {
+            item.ty = Type::new(status);
+            item.time = std::time::SystemTime::now();
+            store.update(id, &item)?;
+            n += 1;
+        }
+    }
+    if !removed.is_empty() {
+        store.remove_all(&removed)?;
+        n += removed.len();
+    }
+    Ok(n)
+}
+
+/// Shows the idea in the pager until it quits, applying the edit, toggle
+/// and delete keys on the way, and tells what changed last.
+fn view_idea(store: &Store, interaction: &mut II, pair: ItemPair, icons: Icons) -> Result<String> {
+    let ItemPair { id, mut item } = pair;
+    let mut done = String::new();
+    loop {
+        let related = store.related(&id, &item)?;
+        match interaction.view_item(item.clone(), related)? {
+            ViewOp::Edit => {
+                item = interaction.update_item(item)?;
+                store.update(id.clone(), &item)?;
+                done = format!("#{} updated", id);
+            }
+            ViewOp::Toggle => done = toggle_status(store, &id, &mut item, icons)?,
+            ViewOp::Delete => {
+                if let Is::Yes = interaction.confirm_again()? {
+                    store.remove(id.clone())?;
+                    return Ok(format!("#{} deleted", id));
+                }
+            }
+            ViewOp::Quit => return Ok(done),
+        }
+    }
+}
+
+fn operate(
+    store: &Store,
+    interaction: &mut II,
+    op: Op,
+    pair: ItemPair,
+    icons: Icons,
+) -> Result<()> {
+    let ItemPair { id, mut item } = pair;
+    match op {
+        Op::Retrieve => {
+            view_idea(store, interaction, ItemPair { id, item }, icons)?;
+        }
+        Op::Delete => {
+            if let Is::Yes = interaction.confirm_again()? {
+                store.remove(id)?
+            }
+        }
+        Op::Update => {
+            item = interaction.update_item(item)?;
+            store.update(id, &item)?
+        }
+        Op::Check => {
+            let n = interaction.check_subtask(&item)?;
+            item.check(n, store.config()?.auto_complete)?;
+            store.update(id, &item)?
+        }
+        _ => {}
     }
+    Ok(())
 }
@@ -0,0 +1,486 @@
+use crate::{
+    date::{parse_since, Date},
+    error::{Error, Result},
+    item::Item,
+    status::*,
+};
+use std::{cmp::Ordering, time::SystemTime};
+use unicode_width::UnicodeWidthStr;
+
+const FIELDS: &[&str; 10] = &[
+    "type",
+    "status",
+    "summary",
+    "detail",
+    "label",
+    "url",
+    "created",
+    "updated",
+    "completed",
+    "due",
+];
+const OPS: &[&str; 6] = &["<=", ">=", ":", "~", "<", ">"];
+
+/// A `--where` expression, such as
+/// `type:todo and (summary~"release" or label:urgent) and created>2026-09-01`.
+#[derive(PartialEq, Debug)]
+pub enum Expr {
+    And(Box<Expr>, Box<Expr>),
+    Or(Box<Expr>, Box<Expr>),
+    Not(Box<Expr>),
+    Test(Test),
+}
+
+#[derive(PartialEq, Debug)]
+pub enum Test {
+    Type(String),
+    /// `status:done` or `status:undone`, which only todos pass.
+    Done(bool),
+    /// `field:text` is equal to, `field~text` contains the text, ignoring case.
+    Text {
+        field: TextField,
+        contains: bool,
+        text: String,
+    },
+    Time {
+        field: TimeField,
+        op: Op,
+        when: When,
+    },
+}
+
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum TextField {
+    Summary,
+    Detail,
+    Label,
+    Url,
+}
+
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum TimeField {
+    Created,
+    Updated,
+    Completed,
+    Due,
+}
+
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum Op {
+    Eq,
+    Lt,
+    Gt,
+    Le,
+    Ge,
+}
+
+/// A date compares by day, a window such as `7d` by the exact time.
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum When {
+    Day(Date),
+    Time(SystemTime),
+}
+
+impl Expr {
+    pub fn matches(&self, item: &Item) -> bool {
+        match self {
+            Expr::And(a, b) => a.matches(item) && b.matches(item),
+            Expr::Or(a, b) => a.matches(item) || b.matches(item),
+            Expr::Not(a) => !a.matches(item),
+            Expr::Test(test) => test.matches(item),
+        }
+    }
+}
+
+impl Test {
+    fn matches(&self, item: &Item) -> bool {
+        match self {
+            Test::Type(ty) => item.ty.desc() == ty,
+            Test::Done(done) => {
+                let status = if *done { DONE } else { UNDONE };
+                item.ty.status() == status
+            }
+            Test::Text {
+                field,
+                contains,
+                text,
+            } => {
+                let texts: Vec<&str> = match field {
+                    TextField::Summary => vec![&item.summary],
+                    TextField::Detail => item.detail.iter().map(String::as_str).collect(),
+                    TextField::Label => item.labels.iter().map(String::as_str).collect(),
+                    TextField::Url => item.urls.iter().map(|r| r.url.as_str()).collect(),
+                };
+                texts
+                    .into_iter()
+                    .map(str::to_lowercase)
+                    .any(|t| match contains {
+                        true => t.contains(text.as_str()),
+                        false => &t == text,
+                    })
+            }
+            Test::Time { field, op, when } => {
+                let time = match field {
+                    TimeField::Created => Some(item.created_at()),
+                    TimeField::Updated => Some(item.time),
+                    TimeField::Completed => item.completed_at(),
+                    TimeField::Due => item.due.map(Date::to_time),
+                };
+                time.is_some_and(|time| op.holds(when.compare(time)))
+            }
+        }
+    }
+}
+
+impl Op {
+    fn holds(self, ordering: Ordering) -> bool {
+        match self {
+            Op::Eq => ordering == Ordering::Equal,
+            Op::Lt => ordering == Ordering::Less,
+            Op::Gt => ordering == Ordering::Greater,
+            Op::Le => ordering != Ordering::Greater,
+            Op::Ge => ordering != Ordering::Less,
+        }
+    }
+}
+
+impl When {
+    /// How `time` compares to this.
+    fn compare(self, time: SystemTime) -> Ordering {
+        match self {
+            When::Day(day) => Date::from_time(time).cmp(&day),
+            When::Time(when) => time.cmp(&when),
+        }
+    }
+}
+
+/// Parses `input`, counting windows such as `7d` back from `now`. Errors
+/// point at the offending token.
+pub fn parse(input: &str, now: SystemTime) -> Result<Expr> {
+    let mut parser = Parser {
+        input,
+        tokens: tokens(input)?,
+        pos: 0,
+        now,
+    };
+    let expr = parser.or()?;
+    let token = parser.next();
+    if token.kind != Kind::End {
+        return Err(caret(input, &token, "expected `and`, `or` or the end"));
+    }
+    Ok(expr)
+}
+
+#[derive(Clone, PartialEq, Debug)]
+enum Kind {
+    Word(String),
+    Quoted(String),
+    Op(&'static str),
+    Open,
+    Close,
+    End,
+}
+
+/// A token and its byte range in the input.
+#[derive(Clone, Debug)]
+struct Token {
+    kind: Kind,
+    start: usize,
+    end: usize,
+}
+
+fn tokens(input: &str) -> Result<Vec<Token>> {
+    let mut tokens = vec![];
+    let mut chars = input.char_indices().peekable();
+    while let Some(&(start, c)) = chars.peek() {
+        let rest = &input[start..];
+        let after_op = matches!(
+            tokens.last(),
+            Some(Token {
+                kind: Kind::Op(_),
+                ..
+            })
+        );
+        let kind = if c.is_whitespace() {
+            chars.next();
+            continue;
+        } else if after_op && !"()\"".contains(c) {
+            // a value such as `https://x.com` may hold operator characters
+            let mut word = String::new();
+            while let Some(&(_, c)) = chars.peek() {
+                if c.is_whitespace() || c == ')' {
+                    break;
+                }
+                word.push(c);
+                chars.next();
+            }
+            Kind::Word(word)
+        } else if c == '(' || c == ')' {
+            chars.next();
+            if c == '(' {
+                Kind::Open
+            } else {
+                Kind::Close
+            }
+        } else if let Some(op) = OPS.iter().find(|op| rest.starts_with(*op)) {
+            chars.nth(op.len() - 1);
+            Kind::Op(op)
+        } else if c == '"' {
+            chars.next();
+            let mut text = String::new();
+            loop {
+                match chars.next() {
+                    Some((_, '"')) => break,
+                    Some((_, '\\')) => text.extend(chars.next().map(|(_, c)| c)),
+                    Some((_, c)) => text.push(c),
+                    None => {
+                        let token = Token {
+                            kind: Kind::End,
+                            start,
+                            end: input.len(),
+                        };
+                        return Err(caret(input, &token, "unterminated string"));
+                    }
+                }
+            }
+            Kind::Quoted(text)
+        } else {
+            let mut word = String::new();
+            while let Some(&(_, c)) = chars.peek() {
+                if c.is_whitespace() || "()\"".contains(c) || OPS.iter().any(|op| op.starts_with(c))
+                {
+                    break;
+                }
+                word.push(c);
+                chars.next();
+            }
+            Kind::Word(word)
+        };
+        let end = chars.peek().map_or(input.len(), |&(i, _)| i);
+        tokens.push(Token { kind, start, end });
+    }
+    tokens.push(Token {
+        kind: Kind::End,
+        start: input.len(),
+        end: input.len(),
+    });
+    Ok(tokens)
+}
+
+/// `message`, then the input with the token underlined.
+fn caret(input: &str, token: &Token, message: &str) -> Error {
+    let indent = input[..token.start].width();
+    let width = input[token.start..token.end].width().max(1);
+    Error::StringError(format!(
+        "{}\n  {}\n  {}{}",
+        message,
+        input,
+        " ".repeat(indent),
+        "^".repeat(width)
+    ))
+}
+
+struct Parser<'a> {
+    input: &'a str,
+    tokens: Vec<Token>,
+    pos: usize,
+    now: SystemTime,
+}
+
+impl Parser<'_> {
+    fn next(&mut self) -> Token {
+        let token = self.tokens[self.pos].clone();
+        if token.kind != Kind::End {
+            self.pos += 1;
+        }
+        token
+    }
+
+    /// Skips the keyword if it comes next.
+    fn keyword(&mut self, keyword: &str) -> bool {
+        match &self.tokens[self.pos].kind {
+            Kind::Word(word) if word.eq_ignore_ascii_case(keyword) => {
+                self.pos += 1;
+                true
+            }
+            _ => false,
+        }
+    }
+
+    fn or(&mut self) -> Result<Expr> {
+        let mut expr = self.and()?;
+        while self.keyword("or") {
+            expr = Expr::Or(Box::new(expr), Box::new(self.and()?));
+        }
+        Ok(expr)
+    }
+
+    fn and(&mut self) -> Result<Expr> {
+        let mut expr = self.not()?;
+        while self.keyword("and") {
+            expr = Expr::And(Box::new(expr), Box::new(self.not()?));
+        }
+        Ok(expr)
+    }
+
+    fn not(&mut self) -> Result<Expr> {
+        if self.keyword("not") {
+            return Ok(Expr::Not(Box::new(self.not()?)));
+        }
+        self.atom()
+    }
+
+    fn atom(&mut self) -> Result<Expr> {
+        let token = self.next();
+        match &token.kind {
+            Kind::Open => {
+                let expr = self.or()?;
+                let close = self.next();
+                if close.kind != Kind::Close {
+                    return Err(caret(self.input, &close, "expected `)`"));
+                }
+                Ok(expr)
+            }
+            Kind::Word(field) => {
+                let field = field.to_lowercase();
+                if !FIELDS.contains(&field.as_str()) {
+                    let message = format!(
+                        "unknown field `{}`, expect one of {}",
+                        field,
+                        FIELDS.join(", ")
+                    );
+                    return Err(caret(self.input, &token, &message));
+                }
+                let op = self.next();
+                let name = match op.kind {
+                    Kind::Op(name) => name,
+                    _ => {
+                        let message = format!("expected `:`, `~`, `<` or `>` after `{}`", field);
+                        return Err(caret(self.input, &op, &message));
+                    }
+                };
+                let value = self.next();
+                match value.kind.clone() {
+                    Kind::Word(text) | Kind::Quoted(text) => self
+                        .test(&field, (name, &op), (text, &value))
+                        .map(Expr::Test),
+                    _ => {
+                        let message = format!("expected a value after `{}`", name);
+                        Err(caret(self.input, &value, &message))
+                    }
+                }
+            }
+            _ => Err(caret(self.input, &token, "expected a field, `not` or `(`")),
+        }
+    }
+
+    fn test(&self, field: &str, op: (&str, &Token), value: (String, &Token)) -> Result<Test> {
+        let ((op, op_token), (text, value_token)) = (op, value);
+        let wrong_value = |message: String| caret(self.input, value_token, &message);
+        let text_field = match field {
+            "summary" => Some(TextField::Summary),
+            "detail" => Some(TextField::Detail),
+            "label" => Some(TextField::Label),
+            "url" => Some(TextField::Url),
+            _ => None,
+        };
+        let time_field = match field {
+            "created" => Some(TimeField::Created),
+            "updated" => Some(TimeField::Updated),
+            "completed" => Some(TimeField::Completed),
+            "due" => Some(TimeField::Due),
+            _ => None,
+        };
+        let text = match field {
+            "type" | "status" => text.to_lowercase(),
+            _ => text,
+        };
+        match (field, op) {
+            ("type", ":") => match TALL.contains(&text.as_str()) {
+                true => Ok(Test::Type(text)),
+                false => Err(wrong_value(format!(
+                    "unknown type `{}`, expect one of {}",
+                    text,
+                    TALL.join(", ")
+                ))),
+            },
+            ("status", ":") => match text.as_str() {
+                "done" => Ok(Test::Done(true)),
+                "undone" => Ok(Test::Done(false)),
+                _ => Err(wrong_value(format!(
+                    "unknown status `{}`, expect done or undone",
+                    text
+                ))),
+            },
+            (_, ":" | "~") if text_field.is_some() => Ok(Test::Text {
+                field: text_field.unwrap(),
+                contains: op == "~",
+                text: text.to_lowercase(),
+            }),
+            (_, ":" | "<" | ">" | "<=" | ">=") if time_field.is_some() => {
+                let when = match text.parse::<Date>() {
+                    Ok(day) => When::Day(day),
+                    Err(_) => When::Time(parse_since(&text, self.now).map_err(wrong_value)?),
+                };
+                let op = match op {
+                    ":" => Op::Eq,
+                    "<" => Op::Lt,
+                    ">" => Op::Gt,
+                    "<=" => Op::Le,
+                    _ => Op::Ge,
+                };
+                Ok(Test::Time {
+                    field: time_field.unwrap(),
+                    op,
+                    when,
+                })
+            }
+            _ => Err(caret(
+                self.input,
+                op_token,
+                &format!("`{}` can't be used with `{}`", op, field),
+            )),
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    #[test]
+    fn test_basis() {
+        let now = "2026-10-19".parse::<Date>().unwrap().to_time();
+        let input = r#"type:todo and status:undone and (summary~"release" or label:urgent) and created>2026-09-01"#;
+        let expr = parse(input, now).unwrap();
+
+        let mut item = Item::new(Type::new(UNDONE), "Cut the Release".to_owned());
+        item.created = Some(now);
+        assert!(expr.matches(&item));
+        item.created = Some("2026-09-01".parse::<Date>().unwrap().to_time());
+        assert!(!expr.matches(&item));
+        item.created = Some(now);
+        item.summary = "fix ci".to_owned();
+        assert!(!expr.matches(&item));
+        item.labels = vec!["Urgent".to_owned()];
+        assert!(expr.matches(&item));
+
+        item.time = now;
+        let not = parse("not type:todo or updated<=7d", now).unwrap();
+        assert!(matches!(not, Expr::Or(..)));
+        assert!(!not.matches(&item));
+
+        let error = parse("type:todo and summery~x", now).unwrap_err();
+        assert_eq!(
+            error.to_string().lines().last(),
+            Some("                ^^^^^^^")
+        );
+        let error = parse("(label:a or label:b", now).unwrap_err();
+        assert!(error.to_string().starts_with("expected `)`"));
+        assert!(parse("created~2026", now).is_err());
+        assert!(parse("summary:\"open", now).is_err());
+
+        item.urls = vec!["https://x.com".parse().unwrap()];
+        let url = parse("(url:https://x.com) and type:TODO and status:Undone", now).unwrap();
+        assert!(url.matches(&item));
+    }
+}
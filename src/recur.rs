@@ -0,0 +1,137 @@
+use crate::date::Date;
+use serde::{Deserialize, Serialize};
+use std::{
+    convert::TryFrom,
+    fmt::{Display, Formatter, Result},
+    str::FromStr,
+};
+
+const WEEKDAYS: [&str; 7] = ["mon", "tue", "wed", "thu", "fri", "sat", "sun"];
+
+pub const RECUR_HINT: &str = "[option: daily|weekly[:mon,fri]|monthly|every:N]";
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(try_from = "String", into = "String")]
+pub enum Recurrence {
+    Daily,
+    /// Weekly on the given weekdays (0 is Monday), or every 7 days if empty.
+    Weekly(Vec<u32>),
+    Monthly,
+    /// Every N days.
+    Every(u32),
+}
+
+impl Recurrence {
+    /// The first occurrence strictly after `from`.
+    pub fn next(&self, from: Date) -> Date {
+        match self {
+            Recurrence::Daily => from.add_days(1),
+            Recurrence::Weekly(days) if days.is_empty() => from.add_days(7),
+            Recurrence::Weekly(days) => (1..=7)
+                .map(|n| from.add_days(n))
+                .find(|d| days.contains(&d.weekday()))
+                .unwrap(),
+            Recurrence::Monthly => from.add_months(1),
+            Recurrence::Every(n) => from.add_days(*n as i64),
+        }
+    }
+    /// The first occurrence after both `from` and today, so missed
+    /// occurrences are skipped rather than piling up.
+    pub fn upcoming(&self, from: Date) -> Date {
+        let today = Date::today();
+        let mut next = self.next(from);
+        while next <= today {
+            next = self.next(next);
+        }
+        next
+    }
+}
+
+impl Display for Recurrence {
+    fn fmt(&self, f: &mut Formatter<'_>) -> Result {
+        let s = match self {
+            Recurrence::Daily => "daily".to_owned(),
+            Recurrence::Weekly(days) if days.is_empty() => "weekly".to_owned(),
+            Recurrence::Weekly(days) => format!(
+                "weekly:{}",
+                days.iter()
+                    .map(|d| WEEKDAYS[*d as usize])
+                    .collect::<Vec<_>>()
+                    .join(",")
+            ),
+            Recurrence::Monthly => "monthly".to_owned(),
+            Recurrence::Every(n) => format!("every:{}", n),
+        };
+        f.pad(s.as_str())
+    }
+}
+
+impl FromStr for Recurrence {
+    type Err = String;
+    fn from_str(s: &str) -> std::result::Result<Recurrence, String> {
+        let err = || format!("invalid recurrence `{}`, expect {}", s, RECUR_HINT);
+        let s = s.trim().to_lowercase();
+        let (kind, arg) = match s.find(':') {
+            Some(n) => (&s[..n], Some(s[n + 1..].trim())),
+            None => (&s[..], None),
+        };
+        Ok(match (kind, arg) {
+            ("daily", None) => Recurrence::Daily,
+            ("weekly", None) => Recurrence::Weekly(vec![]),
+            ("weekly", Some(arg)) => {
+                let mut days = arg
+                    .split(',')
+                    .map(|d| {
+                        let d = d.trim();
+                        WEEKDAYS
+                            .iter()
+                            .position(|w| d.len() >= 3 && w.starts_with(&d[..3]))
+                            .map(|n| n as u32)
+                            .ok_or_else(err)
+                    })
+                    .collect::<std::result::Result<Vec<_>, _>>()?;
+                days.sort_unstable();
+                days.dedup();
+                Recurrence::Weekly(days)
+            }
+            ("monthly", None) => Recurrence::Monthly,
+            ("every", Some(arg)) => match arg.trim_end_matches('d').parse() {
+                Ok(n) if n > 0 => Recurrence::Every(n),
+                _ => return Err(err()),
+            },
+            _ => return Err(err()),
+        })
+    }
+}
+
+impl TryFrom<String> for Recurrence {
+    type Error = String;
+    fn try_from(s: String) -> std::result::Result<Recurrence, String> {
+        s.parse()
+    }
+}
+
+impl From<Recurrence> for String {
+    fn from(recur: Recurrence) -> String {
+        recur.to_string()
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    #[test]
+    fn test_basis() {
+        // a Thursday
+        let from: Date = "2026-10-15".parse().unwrap();
+        let weekly: Recurrence = "weekly:mon,thu".parse().unwrap();
+        assert_eq!(weekly, Recurrence::Weekly(vec![0, 3]));
+        assert_eq!(weekly.to_string(), "weekly:mon,thu");
+        assert_eq!(weekly.next(from).to_string(), "2026-10-19");
+        let every: Recurrence = "every:3d".parse().unwrap();
+        assert_eq!(every.next(from).to_string(), "2026-10-18");
+        assert_eq!(Recurrence::Monthly.next(from).to_string(), "2026-11-15");
+        assert!("weekly:xyz".parse::<Recurrence>().is_err());
+        assert!("every:0".parse::<Recurrence>().is_err());
+    }
+}
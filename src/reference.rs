@@ -0,0 +1,177 @@
+use crate::error::{Error, Result};
+use serde::{Deserialize, Deserializer, Serialize};
+use std::{
+    fmt::{Display, Formatter},
+    process::Command,
+    str::FromStr,
+};
+
+pub const REFERENCE_HINT: &str = "[option: url, path or [title](url), `; ` separated]";
+
+#[derive(Serialize, Deserialize, Clone, PartialEq, Debug)]
+pub struct Reference {
+    pub url: String,
+    #[serde(default)]
+    pub title: Option<String>,
+}
+
+impl Reference {
+    pub fn is_file(&self) -> bool {
+        is_path(&self.url)
+    }
+    /// Launches the reference with the system opener.
+    pub fn open(&self) -> Result<()> {
+        let target = match (self.is_file(), self.url.strip_prefix("~/")) {
+            (true, Some(rest)) => home::home_dir()
+                .map(|home| home.join(rest).to_string_lossy().into_owned())
+                .unwrap_or_else(|| self.url.clone()),
+            _ => self.url.clone(),
+        };
+        let status = opener().arg(&target).status()?;
+        if !status.success() {
+            return Err(Error::StringError(format!("failed to open `{}`", target)));
+        }
+        Ok(())
+    }
+    /// Parses references separated by newlines or `; `.
+    pub fn parse_list(s: &str) -> Result<Vec<Reference>> {
+        s.lines()
+            .flat_map(|line| line.split("; "))
+            .map(str::trim)
+            .filter(|s| !s.is_empty())
+            .map(|s| s.parse().map_err(Error::from))
+            .collect()
+    }
+    pub fn format_list(refs: &[Reference]) -> Option<String> {
+        if refs.is_empty() {
+            return None;
+        }
+        let refs: Vec<_> = refs.iter().map(|r| r.to_string()).collect();
+        Some(refs.join("; "))
+    }
+}
+
+#[cfg(target_os = "macos")]
+fn opener() -> Command {
+    Command::new("open")
+}
+
+#[cfg(target_os = "windows")]
+fn opener() -> Command {
+    let mut cmd = Command::new("cmd");
+    cmd.args(&["/C", "start", ""]);
+    cmd
+}
+
+#[cfg(not(any(target_os = "macos", target_os = "windows")))]
+fn opener() -> Command {
+    Command::new("xdg-open")
+}
+
+fn is_url(s: &str) -> bool {
+    let scheme = match s.find(':') {
+        Some(n) => &s[..n],
+        None => return false,
+    };
+    let rest = &s[scheme.len() + 1..];
+    let mut chars = scheme.chars();
+    chars.next().is_some_and(|c| c.is_ascii_alphabetic())
+        && chars.all(|c| c.is_ascii_alphanumeric() || "+-.".contains(c))
+        && !rest.is_empty()
+        && rest != "//"
+        && !s.contains(char::is_whitespace)
+}
+
+fn is_path(s: &str) -> bool {
+    s.starts_with('/')
+        || s.starts_with("~/")
+        || s.starts_with("./")
+        || s.starts_with("../")
+        || s.get(1..3) == Some(":\\")
+}
+
+impl Display for Reference {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self.title {
+            Some(ref title) => write!(f, "[{}]({})", title, self.url),
+            None => write!(f, "{}", self.url),
+        }
+    }
+}
+
+impl FromStr for Reference {
+    type Err = String;
+    fn from_str(s: &str) -> std::result::Result<Reference, String> {
+        let s = s.trim();
+        let (title, url) = match s.find("](") {
+            Some(n) if s.starts_with('[') && s.ends_with(')') => (
+                Some(s[1..n].trim().to_owned()),
+                s[n + 2..s.len() - 1].trim(),
+            ),
+            _ => (None, s),
+        };
+        if !is_path(url) && !is_url(url) {
+            return Err(format!(
+                "invalid reference `{}`, expect an url or a path starting with `/`, `~/`, `./`, `../` or a drive such as `C:\\`",
+                url
+            ));
+        }
+        Ok(Reference {
+            url: url.to_owned(),
+            title: title.filter(|t| !t.is_empty()),
+        })
+    }
+}
+
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum Urls {
+    Legacy(Option<String>),
+    List(Vec<Reference>),
+}
+
+/// Accepts both the old `url` field, a newline separated string, and a list
+/// of references.
+pub fn deserialize_urls<'de, D>(deserializer: D) -> std::result::Result<Vec<Reference>, D::Error>
+where
+    D: Deserializer<'de>,
+{
+    Ok(match Urls::deserialize(deserializer)? {
+        Urls::Legacy(url) => url
+            .iter()
+            .flat_map(|s| s.lines())
+            .map(str::trim)
+            .filter(|s| !s.is_empty())
+            .map(|s| {
+                s.parse().unwrap_or_else(|_| Reference {
+                    url: s.to_owned(),
+                    title: None,
+                })
+            })
+            .collect(),
+        Urls::List(refs) => refs,
+    })
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    #[test]
+    fn test_basis() {
+        let refs =
+            Reference::parse_list("[docs](https://docs.rs); ./notes.md\nmailto:a@b.c").unwrap();
+        assert_eq!(refs[0].title.as_deref(), Some("docs"));
+        assert_eq!(refs[0].url, "https://docs.rs");
+        assert!(refs[1].is_file());
+        assert_eq!(refs.len(), 3);
+        assert!("not an url".parse::<Reference>().is_err());
+        assert!("Cargo.toml".parse::<Reference>().is_err());
+        assert!("C:\\notes.md".parse::<Reference>().unwrap().is_file());
+        assert!("http://".parse::<Reference>().is_err());
+        let old: Vec<Reference> = deserialize_urls(&mut serde_json::Deserializer::from_str(
+            r#""a.com\nhttp://b""#,
+        ))
+        .unwrap();
+        assert_eq!(old.len(), 2);
+    }
+}
@@ -0,0 +1,211 @@
+use crate::{date::Date, item::Item, status::*};
+use serde::Serialize;
+use std::collections::BTreeMap;
+
+const BAR_WIDTH: usize = 30;
+const TOP_LABELS: usize = 10;
+const SECS_PER_DAY: f64 = 24.0 * 60.0 * 60.0;
+
+#[derive(Serialize)]
+pub struct Stats {
+    /// Items of each type, todos split into `todo` (undone) and `done`.
+    pub statuses: BTreeMap<String, usize>,
+    /// Done todos out of all todos.
+    pub completion_rate: Option<f64>,
+    /// Days from capture to done, over todos with both times recorded.
+    pub average_days_to_done: Option<f64>,
+    pub weeks: Vec<Week>,
+    /// The most used labels, most used first.
+    pub labels: Vec<Label>,
+}
+
+#[derive(Serialize, PartialEq, Debug)]
+pub struct Label {
+    pub name: String,
+    pub count: usize,
+}
+
+#[derive(Serialize)]
+pub struct Week {
+    /// Monday of the week.
+    pub start: Date,
+    pub captured: usize,
+    pub completed: usize,
+    /// Done todos out of the todos captured by the end of the week.
+    pub completion_rate: Option<f64>,
+}
+
+impl Stats {
+    /// Stats of `items` over the `weeks` weeks up to the one of `today`.
+    pub fn collect(items: &[Item], today: Date, weeks: usize) -> Stats {
+        let mut statuses = BTreeMap::new();
+        let mut labels = BTreeMap::new();
+        for item in items {
+            let name = match item.ty.status() {
+                DONE => "done",
+                _ => item.ty.desc().as_str(),
+            };
+            *statuses.entry(name.to_owned()).or_insert(0) += 1;
+            for label in &item.labels {
+                *labels.entry(label.clone()).or_insert(0) += 1;
+            }
+        }
+        let todos: Vec<_> = items.iter().filter(|i| i.ty.desc() == TTODO).collect();
+        let done = todos.iter().filter(|i| i.ty.status() == DONE).count();
+        let days: Vec<f64> = todos
+            .iter()
+            .filter_map(|i| i.completed?.duration_since(i.created?).ok())
+            .map(|d| d.as_secs_f64() / SECS_PER_DAY)
+            .collect();
+
+        let monday = today.add_days(-(today.weekday() as i64));
+        let weeks = (0..weeks)
+            .rev()
+            .map(|n| {
+                let start = monday.add_days(-7 * n as i64);
+                let end = start.add_days(7);
+                let within = |date: Date| start <= date && date < end;
+                let captured = |i: &&&Item| Date::from_time(i.created_at()) < end;
+                let todos_then = todos.iter().filter(captured).count();
+                let done_then = todos
+                    .iter()
+                    .filter_map(|i| i.completed_at())
+                    .filter(|&t| Date::from_time(t) < end)
+                    .count();
+                Week {
+                    start,
+                    captured: items
+                        .iter()
+                        .filter(|i| within(Date::from_time(i.created_at())))
+                        .count(),
+                    completed: todos
+                        .iter()
+                        .filter_map(|i| i.completed_at())
+                        .filter(|&t| within(Date::from_time(t)))
+                        .count(),
+                    completion_rate: rate(done_then, todos_then),
+                }
+            })
+            .collect();
+
+        let mut labels: Vec<_> = labels.into_iter().collect();
+        labels.sort_by(|a, b| b.1.cmp(&a.1).then_with(|| a.0.cmp(&b.0)));
+        labels.truncate(TOP_LABELS);
+        let labels = labels
+            .into_iter()
+            .map(|(name, count)| Label { name, count })
+            .collect();
+        Stats {
+            statuses,
+            completion_rate: rate(done, todos.len()),
+            average_days_to_done: match days.len() {
+                0 => None,
+                n => Some(days.iter().sum::<f64>() / n as f64),
+            },
+            weeks,
+            labels,
+        }
+    }
+
+    /// The report, its bars drawn with `icons`.
+    pub fn markdown(&self, icons: Icons) -> String {
+        let mut text = String::from("# Statistics\n");
+        let statuses: Vec<_> = self
+            .statuses
+            .iter()
+            .map(|(name, n)| (name.clone(), *n))
+            .collect();
+        text += &table("Items", "status", &statuses, icons);
+        text += "\n## Todos\n";
+        text += &format!("* completion rate: {}\n", percent(self.completion_rate));
+        let days = self
+            .average_days_to_done
+            .map_or("-".to_owned(), |d| format!("{:.1} days", d));
+        text += &format!("* average time to done: {}\n", days);
+        let week_of = |w: &Week| w.start.to_string();
+        let captured: Vec<_> = self
+            .weeks
+            .iter()
+            .map(|w| (week_of(w), w.captured))
+            .collect();
+        text += &table("Captured per week", "week", &captured, icons);
+        let completed: Vec<_> = self
+            .weeks
+            .iter()
+            .map(|w| (week_of(w), w.completed))
+            .collect();
+        text += &table("Completed per week", "week", &completed, icons);
+        text += "\n## Completion rate over time\n|week|rate|\n|:-|-:|\n";
+        for week in &self.weeks {
+            text += &format!("|{}|{}|\n", week.start, percent(week.completion_rate));
+        }
+        let labels: Vec<_> = self
+            .labels
+            .iter()
+            .map(|l| (l.name.clone(), l.count))
+            .collect();
+        text += &table("Labels", "label", &labels, icons);
+        text
+    }
+}
+
+fn rate(part: usize, total: usize) -> Option<f64> {
+    match total {
+        0 => None,
+        total => Some(part as f64 / total as f64),
+    }
+}
+
+fn percent(rate: Option<f64>) -> String {
+    rate.map_or("-".to_owned(), |r| format!("{:.0}%", r * 100.0))
+}
+
+/// A section with a table of `rows` and a bar for each.
+fn table(title: &str, head: &str, rows: &[(String, usize)], icons: Icons) -> String {
+    let mut text = format!("\n## {}\n", title);
+    if rows.is_empty() {
+        return text + "nothing yet\n";
+    }
+    let max = rows.iter().map(|(_, n)| *n).max().unwrap_or(0).max(1);
+    text += &format!("|{}|count||\n|:-|-:|:-|\n", head);
+    let block = icons.choose("█", "#");
+    for (name, n) in rows {
+        let bar = block.repeat((n * BAR_WIDTH).div_ceil(max));
+        text += &format!("|{}|{}|{}|\n", name, n, bar);
+    }
+    text
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use std::time::Duration;
+    #[test]
+    fn test_basis() {
+        let today: Date = "2024-03-13".parse().unwrap();
+        let day = Duration::from_secs(24 * 60 * 60);
+        let mut todo = Item::new(Type::new(DONE), "ship".to_owned());
+        todo.created = Some(today.add_days(-9).to_time());
+        todo.completed = todo.created.map(|t| t + day * 2);
+        todo.labels = vec!["work".to_owned()];
+        let mut open = Item::new(Type::new(UNDONE), "test".to_owned());
+        open.created = Some(today.to_time());
+        open.labels = vec!["work".to_owned(), "ci".to_owned()];
+        let mut idea = Item::new(Type::new(IDEA), "think".to_owned());
+        idea.created = Some(today.to_time());
+
+        let stats = Stats::collect(&[todo, open, idea], today, 2);
+        assert_eq!(stats.statuses["done"], 1);
+        assert_eq!(stats.statuses[TTODO], 1);
+        assert_eq!(stats.completion_rate, Some(0.5));
+        assert_eq!(stats.average_days_to_done, Some(2.0));
+        assert_eq!(stats.weeks[0].start.to_string(), "2024-03-04");
+        assert_eq!((stats.weeks[0].captured, stats.weeks[0].completed), (1, 1));
+        assert_eq!(stats.weeks[0].completion_rate, Some(1.0));
+        assert_eq!(stats.weeks[1].captured, 2);
+        assert_eq!(stats.weeks[1].completion_rate, Some(0.5));
+        assert_eq!(stats.labels[0].name, "work");
+        assert_eq!(stats.labels[0].count, 2);
+        assert!(stats.markdown(Icons::Emoji).contains("|idea|1|"));
+    }
+}
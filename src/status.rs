@@ -5,14 +5,60 @@ use crate::interaction::Searchable;
 
 pub const UNDONE: char = '❎';
 pub const DONE: char = '✅';
-pub const TTODO: &'static str = "todo";
+pub const TTODO: &str = "todo";
 pub const IDEA: char = '🧠';
-pub const TIDEA: &'static str = "idea";
+pub const TIDEA: &str = "idea";
 pub const TIPS: char = '💡';
-pub const TTIPS: &'static str = "tips";
+pub const TTIPS: &str = "tips";
 
+pub const PINNED: char = '📌';
 pub const TALL: &[&str; 3] = &[TTODO, TIDEA, TTIPS];
 
+pub const ICONS: &[&str; 2] = &["emoji", "ascii"];
+
+/// How statuses are shown, they are stored as the emoji regardless.
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub enum Icons {
+    #[default]
+    Emoji,
+    /// `[ ]`, `[x]`, `*`, `!` and `^`, for terminals that can't render emoji.
+    Ascii,
+}
+
+impl Icons {
+    /// The icons called `name`, one of `ICONS`.
+    pub fn named(name: &str) -> Option<Icons> {
+        match name {
+            "emoji" => Some(Icons::Emoji),
+            "ascii" => Some(Icons::Ascii),
+            _ => None,
+        }
+    }
+
+    pub fn icon(self, status: char) -> String {
+        if self == Icons::Emoji {
+            return status.to_string();
+        }
+        match status {
+            UNDONE => "[ ]",
+            DONE => "[x]",
+            IDEA => "*",
+            TIPS => "!",
+            PINNED => "^",
+            _ => "?",
+        }
+        .to_owned()
+    }
+
+    /// `emoji`, or `ascii` in its place.
+    pub fn choose(self, emoji: &'static str, ascii: &'static str) -> &'static str {
+        match self {
+            Icons::Emoji => emoji,
+            Icons::Ascii => ascii,
+        }
+    }
+}
+
 #[derive(Clone, Serialize, Deserialize)]
 pub struct Type {
     status: char,
@@ -26,6 +72,15 @@ impl Type {
             desc: status2desc(status).to_owned(),
         }
     }
+    /// The type named `desc`, todos start undone.
+    pub fn from_desc(desc: &str) -> Option<Type> {
+        match desc {
+            TTODO => Some(Type::new(UNDONE)),
+            TIDEA => Some(Type::new(IDEA)),
+            TTIPS => Some(Type::new(TIPS)),
+            _ => None,
+        }
+    }
     pub fn status(&self) -> char {
         self.status
     }
@@ -41,6 +96,10 @@ impl Type {
             _ => None,
         }
     }
+    /// Flips a todo between done and undone.
+    pub fn toggle(&self) -> Option<char> {
+        self.next_status().or_else(|| self.last_status())
+    }
     pub fn desc(&self) -> &String {
         &self.desc
     }
@@ -48,14 +107,17 @@ impl Type {
 
 impl Display for Type {
     fn fmt(&self, f: &mut Formatter<'_>) -> Result {
-        f.pad(format!("{} {}", self.status, self.desc).as_str())
+        f.pad(&self.line(Icons::default()))
     }
 }
 
 impl Searchable for Type {
-    fn is_match(&self, pat: &String) -> bool {
+    fn is_match(&self, pat: &str) -> bool {
         self.desc.contains(pat)
     }
+    fn line(&self, icons: Icons) -> String {
+        format!("{} {}", icons.icon(self.status), self.desc)
+    }
 }
 
 fn status2desc(s: char) -> &'static str {
@@ -75,5 +137,7 @@ mod test {
         let t = Type::new(UNDONE);
         assert_eq!(t.next_status(), Some(DONE));
         assert_eq!(t.desc(), &TTODO.to_owned());
+        assert_eq!(Icons::Emoji.icon(DONE), "✅");
+        assert_eq!(Icons::Ascii.icon(DONE), "[x]");
     }
 }
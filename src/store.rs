@@ -1,10 +1,34 @@
-use std::{fmt, fmt::Display, fmt::Formatter, path::Path};
+use std::{
+    collections::HashSet,
+    fmt,
+    fmt::Display,
+    fmt::Formatter,
+    path::{Path, PathBuf},
+};
 
 use crate::item::Item;
-use crate::{error::Result, interaction::Searchable};
+use crate::link::Relation;
+use crate::{
+    attachment::Blobs,
+    clipboard::Clip,
+    config::Config,
+    error::{Error, Result},
+    filter::Filter,
+    interaction::{History, Searchable},
+    status::Icons,
+};
 
+const CONFIG_TREE: &str = "config";
+const CONFIG_KEY: &str = "config";
+const HISTORY_KEY: &str = "history";
+const QUERY_TREE: &str = "queries";
+// sled keeps its own `blobs` directory in the data dir
+const ATTACHMENT_DIR: &str = "attachments";
+
+#[derive(Clone)]
 pub struct Store {
     db: sled::Db,
+    dir: PathBuf,
 }
 
 pub struct Iter {
@@ -14,7 +38,8 @@ pub struct Iter {
 impl Store {
     pub fn open(path: impl AsRef<Path>) -> Result<Store> {
         Ok(Store {
-            db: sled::open(path)?,
+            db: sled::open(&path)?,
+            dir: path.as_ref().to_path_buf(),
         })
     }
     pub fn insert(&self, item: &Item) -> Result<()> {
@@ -22,11 +47,17 @@ impl Store {
         self.update(id, item)
     }
     pub fn update(&self, id: String, item: &Item) -> Result<()> {
+        let prev = self.get(id.clone())?;
+        let mut item = item.clone();
+        item.stamp_completion();
+        let next = prev.and_then(|prev| item.next_occurrence(&prev));
         self.db
-            .insert(id, serde_json::to_string(item)?.into_bytes())?;
+            .insert(id, serde_json::to_string(&item)?.into_bytes())?;
+        if let Some(next) = next {
+            self.insert(&next)?;
+        }
         Ok(())
     }
-    #[allow(dead_code)]
     pub fn get(&self, id: String) -> Result<Option<Item>> {
         let value = self
             .db
@@ -39,12 +70,144 @@ impl Store {
             .map(|v| serde_json::from_str(v.as_str()))
             .transpose()?)
     }
-    #[allow(dead_code)]
+    /// Removes the item along with every link pointing at it, and the
+    /// attachments nothing else refers to.
     pub fn remove(&self, id: String) -> Result<()> {
-        self.db.remove(id)?;
+        self.remove_all(&[id])
+    }
+    /// Removes the items like `remove`, scanning the store only once.
+    pub fn remove_all(&self, ids: &[String]) -> Result<()> {
+        for id in ids {
+            self.db.remove(id)?;
+        }
+        for pair in self.iter() {
+            let ItemPair {
+                id: other,
+                mut item,
+            } = pair?;
+            let mut changed = false;
+            for id in ids {
+                changed |= item.unlink(id);
+            }
+            if changed {
+                self.db
+                    .insert(other, serde_json::to_string(&item)?.into_bytes())?;
+            }
+        }
+        self.db.flush()?;
+        self.gc()?;
+        Ok(())
+    }
+    pub fn blobs(&self) -> Blobs {
+        Blobs::new(self.dir.join(ATTACHMENT_DIR))
+    }
+    /// Drops attachment blobs no item refers to, returns how many.
+    pub fn gc(&self) -> Result<usize> {
+        let mut referenced = HashSet::new();
+        for pair in self.iter() {
+            referenced.extend(pair?.item.attachments.into_iter().map(|a| a.hash));
+        }
+        self.blobs().gc(&referenced)
+    }
+    /// Links `from` to `to`, refusing self links and parent cycles.
+    pub fn link(&self, from: String, to: String, rel: Relation) -> Result<()> {
+        let get = |id: &String| {
+            self.get(id.clone())?
+                .ok_or_else(|| Error::StringError(format!("no idea with id `{}`", id)))
+        };
+        let mut item = get(&from)?;
+        let mut parent = Some(to.clone());
+        while let Some(id) = parent {
+            if id == from {
+                return Err(Error::StringError(format!(
+                    "linking `{}` to `{}` makes a cycle",
+                    from, to
+                )));
+            }
+            parent = get(&id)?.parent().cloned();
+            if rel != Relation::Parent {
+                break;
+            }
+        }
+        item.link(rel, to);
+        self.update(from, &item)
+    }
+    /// Items linked from `item`, and items linking to `id`, each with the
+    /// relation read from `item`'s side.
+    pub fn related(&self, id: &str, item: &Item) -> Result<Vec<(String, ItemPair)>> {
+        let mut related = vec![];
+        for link in &item.links {
+            if let Some(target) = self.get(link.id.clone())? {
+                let pair = ItemPair {
+                    id: link.id.clone(),
+                    item: target,
+                };
+                related.push((link.rel.to_string(), pair));
+            }
+        }
+        for pair in self.iter() {
+            let pair = pair?;
+            let backlinks: Vec<_> = pair.item.links.iter().filter(|l| l.id == id).collect();
+            for link in backlinks {
+                related.push((link.rel.inverse().to_owned(), pair.clone()));
+            }
+        }
+        Ok(related)
+    }
+    pub fn config(&self) -> Result<Config> {
+        let value = self.db.open_tree(CONFIG_TREE)?.get(CONFIG_KEY)?;
+        Ok(match value {
+            Some(v) => serde_json::from_slice(&v)?,
+            None => Config::default(),
+        })
+    }
+    pub fn set_config(&self, config: &Config) -> Result<()> {
+        self.db
+            .open_tree(CONFIG_TREE)?
+            .insert(CONFIG_KEY, serde_json::to_vec(config)?)?;
         self.db.flush()?;
         Ok(())
     }
+    pub fn history(&self) -> Result<History> {
+        let value = self.db.open_tree(CONFIG_TREE)?.get(HISTORY_KEY)?;
+        Ok(match value {
+            Some(v) => serde_json::from_slice(&v)?,
+            None => History::default(),
+        })
+    }
+    pub fn set_history(&self, history: &History) -> Result<()> {
+        self.db
+            .open_tree(CONFIG_TREE)?
+            .insert(HISTORY_KEY, serde_json::to_vec(history)?)?;
+        self.db.flush()?;
+        Ok(())
+    }
+    /// Saved queries by name.
+    pub fn queries(&self) -> Result<Vec<(String, Filter)>> {
+        let mut queries = vec![];
+        for x in self.db.open_tree(QUERY_TREE)?.iter() {
+            let (k, v) = x?;
+            queries.push((String::from_utf8(k.to_vec())?, serde_json::from_slice(&v)?));
+        }
+        Ok(queries)
+    }
+    pub fn query(&self, name: &str) -> Result<Option<Filter>> {
+        let value = self.db.open_tree(QUERY_TREE)?.get(name)?;
+        Ok(value.map(|v| serde_json::from_slice(&v)).transpose()?)
+    }
+    pub fn save_query(&self, name: &str, filter: &Filter) -> Result<()> {
+        self.db
+            .open_tree(QUERY_TREE)?
+            .insert(name, serde_json::to_vec(filter)?)?;
+        self.db.flush()?;
+        Ok(())
+    }
+    /// Removes a saved query, returns whether there was one.
+    pub fn remove_query(&self, name: &str) -> Result<bool> {
+        let removed = self.db.open_tree(QUERY_TREE)?.remove(name)?.is_some();
+        self.db.flush()?;
+        Ok(removed)
+    }
     pub fn iter(&self) -> Iter {
         Iter {
             iter: self.db.iter(),
@@ -78,7 +241,16 @@ impl Display for ItemPair {
 }
 
 impl Searchable for ItemPair {
-    fn is_match(&self, pat: &String) -> bool {
+    fn is_match(&self, pat: &str) -> bool {
         self.item.is_match(pat)
     }
+    fn flaggable(&self) -> bool {
+        true
+    }
+    fn line(&self, icons: Icons) -> String {
+        self.item.line(icons)
+    }
+    fn clip(&self, part: Clip) -> Option<String> {
+        self.item.clip(part)
+    }
 }